@@ -0,0 +1,160 @@
+/*  -----------------------------------------------------------------
+    Benchmarks the two json-ingesting stages on a synthetic tree shaped like the Hall-Hoag one,
+    built by fixtures::generate() with the real tree's rough proportions (organization-files,
+    placeholders, a few malformed trackers, etc).
+    Each stage runs twice on the same tree: `reused_buffer` is the crate's code; `string_per_file` is the
+    earlier path, reading each file into a fresh `String` before parsing it, for comparison.
+
+    % cargo bench --bench ingest
+    % BENCH_ITEMS=2000 cargo bench --bench ingest   (items per collection; default 500, with 20 collections)
+    -----------------------------------------------------------------
+*/
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use parse_ocr_tracker::fixtures::{self, FixtureSpec};
+use parse_ocr_tracker::helper::{
+    find_json_files, join_pid, make_id_to_pid_map, parse_key_from_path, process_files, IngestInfo,
+    IngestTrackers, PathResults,
+};
+use parse_ocr_tracker::throttle::IoThrottle;
+use parse_ocr_tracker::{schema, Config, Record};
+use rayon::prelude::*;
+use serde::Deserialize;
+use std::{collections::BTreeMap, env, fs, hint::black_box, path::PathBuf};
+use tempfile::TempDir;
+
+const COLLECTION_COUNT: usize = 20;
+
+#[derive(Deserialize)]
+struct IngestTracker {
+    pid: String,
+    #[serde(flatten)]
+    info: IngestInfo,
+}
+
+// make_id_to_pid_map() (and its ingest-info map), reading each file into a fresh String
+fn string_per_file_ingest_trackers(paths: Vec<PathBuf>, throttle: &IoThrottle) -> IngestTrackers {
+    let entries: Vec<(String, IngestTracker)> = paths
+        .par_iter()
+        .filter_map(|path| {
+            let contents: String = {
+                let _read_permit = throttle.acquire();
+                fs::read_to_string(path).ok()?
+            };
+            let tracker: IngestTracker = serde_json::from_str(&contents).ok()?;
+            Some((parse_key_from_path(path), tracker))
+        })
+        .collect();
+    let mut ingest_trackers = IngestTrackers::default();
+    for (key, tracker) in entries {
+        if tracker.info != IngestInfo::default() {
+            ingest_trackers.ingest_info_map.insert(key.clone(), tracker.info);
+        }
+        ingest_trackers.id_to_pid_map.insert(key, tracker.pid);
+    }
+    ingest_trackers
+}
+
+// process_files() (without a checkpoint), reading each file into a fresh String
+fn string_per_file_records(
+    paths: Vec<PathBuf>, id_to_pid_map: &BTreeMap<String, String>, pid_url_base: &str, throttle: &IoThrottle,
+) -> PathResults {
+    let no_resolved_pids: BTreeMap<String, String> = BTreeMap::new();
+    let results: Vec<(&PathBuf, Option<Record>)> = paths
+        .par_iter()
+        .map(|path| {
+            let contents: Option<String> = {
+                let _read_permit = throttle.acquire();
+                fs::read_to_string(path).ok()
+            };
+            let record: Option<Record> = contents.and_then(|contents| {
+                let mut record: Record = schema::parse_ocr_tracker(contents.as_bytes(), path).ok()?;
+                join_pid(
+                    &mut record,
+                    &parse_key_from_path(path),
+                    id_to_pid_map,
+                    &no_resolved_pids,
+                    pid_url_base,
+                );
+                Some(record)
+            });
+            (path, record)
+        })
+        .collect();
+    let mut path_results = PathResults {
+        extracted_data_files: Vec::new(),
+        record_paths: Vec::new(),
+        rejected_paths: Vec::new(),
+        unprocessed_count: 0,
+        resumed_count: 0,
+    };
+    for (path, record) in results {
+        match record {
+            Some(record) => {
+                path_results.extracted_data_files.push(record);
+                path_results.record_paths.push(path.clone());
+            }
+            None => path_results.rejected_paths.push(path.clone()),
+        }
+    }
+    path_results
+}
+
+fn ingest_benchmarks(c: &mut Criterion) {
+    let items_per_collection: usize =
+        env::var("BENCH_ITEMS").ok().and_then(|n| n.parse().ok()).unwrap_or(500);
+    let dir = TempDir::new().unwrap();
+    fixtures::generate(
+        dir.path(),
+        &FixtureSpec::scaled(COLLECTION_COUNT, COLLECTION_COUNT * items_per_collection),
+    )
+    .unwrap();
+
+    let config = Config::default();
+    let paths = find_json_files(dir.path(), &config);
+    let throttle = IoThrottle::new(None, None);
+    let id_to_pid_map = make_id_to_pid_map(paths.ingest_tracker_paths.clone(), &throttle);
+
+    let mut group = c.benchmark_group("ingest");
+    group.sample_size(20);
+
+    group.throughput(Throughput::Elements(paths.ingest_tracker_paths.len() as u64));
+    group.bench_function(BenchmarkId::new("make_id_to_pid_map", "string_per_file"), |b| {
+        b.iter(|| {
+            black_box(string_per_file_ingest_trackers(
+                paths.ingest_tracker_paths.clone(),
+                &throttle,
+            ))
+        })
+    });
+    group.bench_function(BenchmarkId::new("make_id_to_pid_map", "reused_buffer"), |b| {
+        b.iter(|| black_box(make_id_to_pid_map(paths.ingest_tracker_paths.clone(), &throttle)))
+    });
+
+    group.throughput(Throughput::Elements(paths.ocr_tracker_paths.len() as u64));
+    group.bench_function(BenchmarkId::new("process_files", "string_per_file"), |b| {
+        b.iter(|| {
+            black_box(string_per_file_records(
+                paths.ocr_tracker_paths.clone(),
+                &id_to_pid_map,
+                &config.pid_url_base,
+                &throttle,
+            ))
+        })
+    });
+    group.bench_function(BenchmarkId::new("process_files", "reused_buffer"), |b| {
+        b.iter(|| {
+            black_box(process_files(
+                paths.ocr_tracker_paths.clone(),
+                &id_to_pid_map,
+                &BTreeMap::new(),
+                &config.pid_url_base,
+                &throttle,
+                None,
+            ))
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, ingest_benchmarks);
+criterion_main!(benches);
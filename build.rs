@@ -8,7 +8,7 @@ fn main() {
     println!("start build.rs");
     // get current git commit hash ----------------------------------
     let output =
-        Command::new("git").args(&["rev-parse", "HEAD"]).output().expect("Failed to execute git command");
+        Command::new("git").args(["rev-parse", "HEAD"]).output().expect("Failed to execute git command");
 
     let git_hash = String::from_utf8_lossy(&output.stdout).trim().to_string();
     let version_string: String = format!("version-{}", git_hash);
@@ -0,0 +1,93 @@
+use clap::{arg, ArgMatches, Command};
+use parse_ocr_tracker::fixtures::{self, FixtureManifest, FixtureSpec};
+use parse_ocr_tracker::TrackerError;
+use std::path::Path;
+
+/*  -----------------------------------------------------------------
+    Builds a synthetic tracker-tree (see fixtures.rs) and prints its manifest-json -- what a scan
+    of the tree should find -- to stdout.
+    Exit-codes: 0 on success; otherwise the failure-category's code (see error.rs).
+    -----------------------------------------------------------------
+*/
+fn main() {
+    let matches = cli().get_matches();
+    let exit_code: i32 = match run(&matches) {
+        Ok(manifest) => match serde_json::to_string_pretty(&manifest) {
+            Ok(manifest_json) => {
+                println!("{}", manifest_json);
+                0
+            }
+            Err(e) => {
+                eprintln!("Error serializing manifest-JSON: {}", e);
+                1
+            }
+        },
+        Err(e) => {
+            eprintln!("{}", e);
+            e.exit_code()
+        }
+    };
+    if exit_code != 0 {
+        std::process::exit(exit_code);
+    }
+}
+
+fn run(matches: &ArgMatches) -> Result<FixtureManifest, TrackerError> {
+    // -- the realistic proportions for the size, then any explicit counts
+    let collections: usize = *matches.get_one::<usize>("collections").unwrap_or(&20);
+    let items: usize = *matches.get_one::<usize>("items").unwrap_or(&10_000);
+    let mut spec = FixtureSpec::scaled(collections, items);
+    let counts: [(&str, &mut usize); 7] = [
+        ("ingest_trackers", &mut spec.ingest_trackers),
+        ("error_trackers", &mut spec.error_trackers),
+        ("organization_files", &mut spec.organization_files),
+        ("junk_files", &mut spec.junk_files),
+        ("dash_placeholders", &mut spec.dash_placeholders),
+        ("malformed_ocr_trackers", &mut spec.malformed_ocr_trackers),
+        ("malformed_ingest_trackers", &mut spec.malformed_ingest_trackers),
+    ];
+    for (name, count) in counts {
+        if let Some(n) = matches.get_one::<usize>(name) {
+            *count = *n;
+        }
+    }
+    if let Some(seed) = matches.get_one::<u64>("seed") {
+        spec.seed = *seed;
+    }
+
+    let output_dir_path: &String = matches.get_one::<String>("output_dir_path").expect("required by clap");
+    fixtures::generate(Path::new(output_dir_path), &spec)
+}
+
+fn cli() -> Command {
+    let about_text = r#"Info...
+  - Builds a synthetic tracker-tree, shaped like the Hall-Hoag one, in `output_dir_path` (which must be missing or empty).
+  - Counts default to the real tree's rough proportions for the given `--collections` and `--items`;
+    each of the other flags overrides one count.
+  - The same counts and `--seed` always build the same tree.
+  - Prints a manifest-json of the expected scan-results: counts, rejected paths, and each record's pid."#;
+    let count = |arg: clap::Arg| arg.value_parser(clap::value_parser!(usize));
+    Command::new("generate_fixtures")
+        .about(about_text)
+        .arg(arg!(-o --output_dir_path <PATH> "where to build the tree").required(true))
+        .arg(count(arg!(--collections <N> "collection-dirs; default 20")))
+        .arg(count(
+            arg!(--items <N> "item-dirs, each with an ocr-tracker; default 10000"),
+        ))
+        .arg(count(arg!(--ingest_trackers <N> "items with an ingest-tracker")))
+        .arg(count(arg!(--error_trackers <N> "items with an error-tracker")))
+        .arg(count(
+            arg!(--organization_files <N> "collections with an organization-file"),
+        ))
+        .arg(count(arg!(--junk_files <N> "non-tracker files")))
+        .arg(count(
+            arg!(--dash_placeholders <N> "ocr-trackers with `\"-\"` orientation- and script-fields"),
+        ))
+        .arg(count(
+            arg!(--malformed_ocr_trackers <N> "unparseable ocr-trackers"),
+        ))
+        .arg(count(
+            arg!(--malformed_ingest_trackers <N> "unparseable or pid-less ingest-trackers"),
+        ))
+        .arg(arg!(--seed <N> "default 1545").value_parser(clap::value_parser!(u64)))
+}
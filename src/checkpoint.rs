@@ -0,0 +1,190 @@
+use crate::error::TrackerError;
+use crate::helper::Record;
+use crate::{log_info, log_warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+pub const CHECKPOINT_FILENAME: &str = "tracker_checkpoint.jsonl";
+
+/*  -----------------------------------------------------------------
+    Represents one line of the checkpoint file: a processed ocr-tracker path,
+    and its Record -- or `null` if the file was rejected (eg an organization-file).
+    -----------------------------------------------------------------
+*/
+#[derive(Debug, Deserialize, Serialize)]
+struct CheckpointEntry {
+    path: PathBuf,
+    record: Option<Record>,
+}
+
+/*  -----------------------------------------------------------------
+    Periodically saves the results of process_files() to `(output_dir)/tracker_checkpoint.jsonl`,
+    so a crashed or interrupted run can be picked up with `--resume` instead of starting over.
+    - Each processed path is appended as a json-line; the file is flushed and synced every `checkpoint_every` files.
+    - On resume, the existing lines are loaded (a torn last line from a crash is dropped), and the file is
+      rewritten with just the good lines before new ones are appended.
+    - process_files() reuses the loaded results in path-order, so the final csv matches an uninterrupted run.
+      Reused records get their pids joined again from the current maps, since an ingest-tracker may have
+      changed in between; the ingest- and review-columns are joined afterwards for every record anyway.
+    - main() removes the file once the csv is saved.
+    -----------------------------------------------------------------
+*/
+pub struct Checkpoint {
+    file_path: PathBuf,
+    previous_results: HashMap<PathBuf, Option<Record>>,
+    reused_count: AtomicUsize, // previous results actually looked up; paths no longer in the tree aren't
+    writer: Mutex<CheckpointWriter>,
+    checkpoint_every: usize,
+}
+
+struct CheckpointWriter {
+    file: BufWriter<File>,
+    unsynced_count: usize,
+}
+
+impl Checkpoint {
+    pub fn open(output_dir: &str, resume: bool, checkpoint_every: usize) -> Result<Checkpoint, TrackerError> {
+        let file_path: PathBuf = Path::new(output_dir).join(CHECKPOINT_FILENAME);
+
+        // -- load previous results
+        let previous_entries: Vec<CheckpointEntry> = if resume {
+            load_entries(&file_path)?
+        } else {
+            if file_path.exists() {
+                log_warn!(
+                    "replacing the existing checkpoint at ``{:?}``; use `--resume` to continue it",
+                    file_path
+                );
+            }
+            Vec::new()
+        };
+        log_info!("loaded {} checkpointed results", previous_entries.len());
+
+        // -- rewrite the file with just the good entries
+        let file = File::create(&file_path).map_err(|e| TrackerError::output(&file_path, e))?;
+        let mut file = BufWriter::new(file);
+        for entry in &previous_entries {
+            write_entry(&mut file, &file_path, entry)?;
+        }
+        file.flush().map_err(|e| TrackerError::output(&file_path, e))?;
+
+        let previous_results: HashMap<PathBuf, Option<Record>> =
+            previous_entries.into_iter().map(|entry| (entry.path, entry.record)).collect();
+        Ok(Checkpoint {
+            file_path,
+            previous_results,
+            reused_count: AtomicUsize::new(0),
+            writer: Mutex::new(CheckpointWriter {
+                file,
+                unsynced_count: 0,
+            }),
+            checkpoint_every,
+        })
+    }
+
+    /// How many checkpointed results have been reused so far.
+    pub fn resumed_count(&self) -> usize {
+        self.reused_count.load(Ordering::Relaxed)
+    }
+
+    /*  -----------------------------------------------------------------
+        Returns the checkpointed result for a path, if it was processed by an earlier run; counts it as reused.
+        -----------------------------------------------------------------
+    */
+    pub fn previous_result(&self, path: &Path) -> Option<Result<Record, PathBuf>> {
+        let previous = self.previous_results.get(path)?;
+        self.reused_count.fetch_add(1, Ordering::Relaxed);
+        Some(match previous {
+            Some(rec) => Ok(rec.clone()),
+            None => Err(path.to_path_buf()),
+        })
+    }
+
+    /*  -----------------------------------------------------------------
+        Appends a newly-processed result; syncs to disk every `checkpoint_every` results.
+        A failed write only logs a warning -- losing the checkpoint shouldn't lose the run.
+        -----------------------------------------------------------------
+    */
+    pub fn save_result(&self, path: &Path, result: &Result<Record, PathBuf>) {
+        let entry = CheckpointEntry {
+            path: path.to_path_buf(),
+            record: result.as_ref().ok().cloned(),
+        };
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(e) = write_entry(&mut writer.file, &self.file_path, &entry) {
+            log_warn!("{}", e);
+            return;
+        }
+        writer.unsynced_count += 1;
+        if writer.unsynced_count >= self.checkpoint_every {
+            writer.unsynced_count = 0;
+            if let Err(e) = sync(&mut writer.file) {
+                log_warn!("Failed to sync checkpoint file ``{:?}``: {}", self.file_path, e);
+            }
+        }
+    }
+
+    /*  -----------------------------------------------------------------
+        Syncs any remaining results; called when process_files() finishes or is interrupted.
+        -----------------------------------------------------------------
+    */
+    pub fn finish(&self) {
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(e) = sync(&mut writer.file) {
+            log_warn!("Failed to sync checkpoint file ``{:?}``: {}", self.file_path, e);
+        }
+    }
+
+    /*  -----------------------------------------------------------------
+        Deletes the checkpoint file; called once the final csv is saved.
+        -----------------------------------------------------------------
+    */
+    pub fn remove(self) {
+        drop(self.writer);
+        if let Err(e) = fs::remove_file(&self.file_path) {
+            log_warn!("Failed to remove checkpoint file ``{:?}``: {}", self.file_path, e);
+        }
+    }
+}
+
+// a missing or unreadable checkpoint is a read-error; `--resume` needs it
+fn load_entries(file_path: &Path) -> Result<Vec<CheckpointEntry>, TrackerError> {
+    let read_error = |e| TrackerError::Read {
+        path: file_path.to_path_buf(),
+        source: e,
+    };
+    let file = File::open(file_path).map_err(read_error)?;
+    let mut entries: Vec<CheckpointEntry> = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line: String = line.map_err(read_error)?;
+        match serde_json::from_str::<CheckpointEntry>(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => {
+                // only expected for the last line, if the run died mid-write
+                log_warn!("skipping unreadable checkpoint line {}: {}", i + 1, e);
+            }
+        }
+    }
+    Ok(entries)
+}
+
+fn write_entry(
+    file: &mut BufWriter<File>, file_path: &Path, entry: &CheckpointEntry,
+) -> Result<(), TrackerError> {
+    let line: String = serde_json::to_string(entry).map_err(|e| TrackerError::output(file_path, e))?;
+    writeln!(file, "{}", line).map_err(|e| TrackerError::output(file_path, e))
+}
+
+fn sync(file: &mut BufWriter<File>) -> std::io::Result<()> {
+    file.flush()?;
+    file.get_ref().sync_data()
+}
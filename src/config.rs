@@ -0,0 +1,341 @@
+use crate::error::TrackerError;
+use crate::helper;
+use crate::log_debug;
+use crate::reviews::ReviewedItems;
+use chrono::format::{Item, StrftimeItems};
+use chrono_tz::Tz;
+use clap::ArgMatches;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/*  -----------------------------------------------------------------
+    Represents all the run-options.
+    - The defaults are the values that used to be compiled in.
+    - Any of them can be set in a toml file passed via `--config path.toml`.
+    - Cli-flags override the file.
+    The merged config is echoed in the output-json so a run can be reproduced.
+    -----------------------------------------------------------------
+*/
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub source_dir_path: Option<String>,
+    pub output_dir_path: Option<String>,
+    pub log_level: Option<String>, // if unset, falls back to the `LOG_LEVEL` envar, then to "warn"
+    pub log_format: Option<String>, // "text" or "json"; if unset, falls back to the `LOG_FORMAT` envar, then to "text"
+    // -- log-file; if unset, falls back to the `LOG_FILE` envar, then to the console
+    pub log_file: Option<String>,
+    pub log_max_bytes: u64, // rotate when the log-file would exceed this; 0 disables rotation
+    pub log_backup_count: usize,
+    pub log_warnings_to_stderr: bool, // when logging to a file, also print warnings to stderr
+    pub dry_run: bool,                // only walk and inventory the source-dir; no parsing, no csv
+    // -- serve-mode (see server.rs); after the run, answers http requests about its results until Ctrl-C
+    pub serve: Option<String>,          // address to listen on, eg "127.0.0.1:8080"
+    pub serve_csv_path: Option<String>, // serve this earlier run's csv instead of scanning
+    // -- watch-mode (see watch.rs); after the run, keeps the csv and summary current until Ctrl-C
+    pub watch: bool,
+    pub watch_debounce_seconds: u64, // changes are batched; one rewrite per interval, at most
+    pub resume: bool,                // continue from the checkpoint in the output-dir
+    pub checkpoint_every: usize,     // files between checkpoint syncs; 0 disables checkpointing
+    pub metrics_file: Option<String>, // Prometheus text-format metrics, for the node-exporter textfile-collector
+    // -- concurrency; `threads` sizes rayon's global pool (unset means rayon's default: one per cpu)
+    pub threads: Option<usize>,
+    pub max_concurrent_reads: Option<usize>, // cap on files open at once, across all threads
+    pub files_per_second: Option<f64>,       // rate-limit on file reads
+    // -- used by find_json_files() to classify paths
+    pub ocr_tracker_suffix: String,
+    pub ingest_tracker_suffix: String,
+    pub error_tracker_marker: String, // matched anywhere in the file-name
+    // -- used by process_files() to build the `pid_url` column
+    pub pid_url_base: String,
+    // -- the optional pid-resolver (see resolver.rs), for items with no usable ingest-tracker; off unless the url is set
+    pub pid_resolver_url: Option<String>, // `{id}` is replaced with the item's key
+    pub pid_resolver_pid_pointer: String, // json-pointer to the pid in the response
+    pub pid_resolver_cache_path: Option<String>, // if unset, `(output_dir_path)/pid_resolver_cache.json`
+    pub pid_resolver_concurrency: usize,  // requests in flight at once
+    pub pid_resolver_timeout_seconds: u64, // per request
+    // -- the optional reviews-csv (see reviews.rs), joined onto the records after parsing
+    pub reviews_path: Option<String>,
+    pub reviewed_items: ReviewedItems, // "keep", "downrank" (to the end of the csv), or "exclude"
+    // -- validation of the records' ocr-statistics (see validation.rs)
+    pub strict: bool, // leave records that fail a check out of the csv, instead of flagging them
+    // -- used by main() for the datestamp; any IANA name, or "UTC"
+    pub timezone: String,
+    pub datetime_format: String, // strftime-format for the output-json's `datetime_stamp`
+    // -- used by save_to_csv(); `{datetime}` is replaced with the run's datestamp in `filename_datetime_format`
+    pub output_filename_pattern: String,
+    pub filename_datetime_format: String,
+    pub extra_columns: Vec<String>, // ocr-tracker fields outside the Record's own, promoted to csv-columns
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            source_dir_path: None,
+            output_dir_path: None,
+            log_level: None,
+            log_format: None,
+            log_file: None,
+            log_max_bytes: 10 * 1024 * 1024,
+            log_backup_count: 5,
+            log_warnings_to_stderr: false,
+            dry_run: false,
+            serve: None,
+            serve_csv_path: None,
+            watch: false,
+            watch_debounce_seconds: 5,
+            resume: false,
+            checkpoint_every: 10_000,
+            metrics_file: None,
+            threads: None,
+            max_concurrent_reads: None,
+            files_per_second: None,
+            ocr_tracker_suffix: "ocr_complete.json".to_string(),
+            ingest_tracker_suffix: "ingest_complete.json".to_string(),
+            error_tracker_marker: "error".to_string(),
+            pid_url_base: "https://repository.library.brown.edu/studio/item/".to_string(),
+            pid_resolver_url: None,
+            pid_resolver_pid_pointer: "/response/docs/0/pid".to_string(),
+            pid_resolver_cache_path: None,
+            pid_resolver_concurrency: 4,
+            pid_resolver_timeout_seconds: 10,
+            reviews_path: None,
+            reviewed_items: ReviewedItems::Downrank,
+            strict: false,
+            timezone: "US/Eastern".to_string(),
+            datetime_format: "%Y-%m-%d_%H:%M:%S_%:z".to_string(),
+            output_filename_pattern: "tracker_output_{datetime}.csv".to_string(),
+            filename_datetime_format: "%Y-%m-%d_%H-%M-%S".to_string(),
+            extra_columns: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /*  -----------------------------------------------------------------
+        Loads the toml file; keys that aren't in the file keep their defaults.
+        -----------------------------------------------------------------
+    */
+    pub fn from_toml_file(path: &str) -> Result<Config, TrackerError> {
+        let contents: String = fs::read_to_string(path).map_err(|e| TrackerError::Read {
+            path: path.into(),
+            source: e,
+        })?;
+        let config: Config = toml::from_str(&contents)
+            .map_err(|e| TrackerError::Config(format!("Failed to parse config file ``{}``: {}", path, e)))?;
+        log_debug!("config loaded from file: {:?}", config);
+        Ok(config)
+    }
+
+    /*  -----------------------------------------------------------------
+        Overrides config-values with any cli-flags that were passed.
+        -----------------------------------------------------------------
+    */
+    pub fn apply_cli_args(&mut self, matches: &ArgMatches) {
+        if let Some(source_dir) = matches.get_one::<String>("source_dir_path") {
+            self.source_dir_path = Some(source_dir.clone());
+        }
+        if let Some(output_dir) = matches.get_one::<String>("output_dir_path") {
+            self.output_dir_path = Some(output_dir.clone());
+        }
+        if let Some(log_level) = matches.get_one::<String>("log_level") {
+            self.log_level = Some(log_level.clone());
+        }
+        if let Some(log_format) = matches.get_one::<String>("log_format") {
+            self.log_format = Some(log_format.clone());
+        }
+        if let Some(log_file) = matches.get_one::<String>("log_file") {
+            self.log_file = Some(log_file.clone());
+        }
+        if let Some(max_bytes) = matches.get_one::<u64>("log_max_bytes") {
+            self.log_max_bytes = *max_bytes;
+        }
+        if let Some(backup_count) = matches.get_one::<usize>("log_backup_count") {
+            self.log_backup_count = *backup_count;
+        }
+        if matches.get_flag("log_warnings_to_stderr") {
+            self.log_warnings_to_stderr = true;
+        }
+        if matches.get_flag("dry_run") {
+            self.dry_run = true;
+        }
+        if let Some(address) = matches.get_one::<String>("serve") {
+            self.serve = Some(address.clone());
+        }
+        if let Some(csv_path) = matches.get_one::<String>("serve_csv_path") {
+            self.serve_csv_path = Some(csv_path.clone());
+        }
+        if matches.get_flag("watch") {
+            self.watch = true;
+        }
+        if let Some(seconds) = matches.get_one::<u64>("watch_debounce_seconds") {
+            self.watch_debounce_seconds = *seconds;
+        }
+        if matches.get_flag("resume") {
+            self.resume = true;
+        }
+        if let Some(checkpoint_every) = matches.get_one::<usize>("checkpoint_every") {
+            self.checkpoint_every = *checkpoint_every;
+        }
+        if let Some(metrics_file) = matches.get_one::<String>("metrics_file") {
+            self.metrics_file = Some(metrics_file.clone());
+        }
+        if let Some(threads) = matches.get_one::<usize>("threads") {
+            self.threads = Some(*threads);
+        }
+        if let Some(max_reads) = matches.get_one::<usize>("max_concurrent_reads") {
+            self.max_concurrent_reads = Some(*max_reads);
+        }
+        if let Some(rate) = matches.get_one::<f64>("files_per_second") {
+            self.files_per_second = Some(*rate);
+        }
+        if let Some(url) = matches.get_one::<String>("pid_resolver_url") {
+            self.pid_resolver_url = Some(url.clone());
+        }
+        if let Some(cache_path) = matches.get_one::<String>("pid_resolver_cache_path") {
+            self.pid_resolver_cache_path = Some(cache_path.clone());
+        }
+        if let Some(concurrency) = matches.get_one::<usize>("pid_resolver_concurrency") {
+            self.pid_resolver_concurrency = *concurrency;
+        }
+        if let Some(reviews_path) = matches.get_one::<String>("reviews_path") {
+            self.reviews_path = Some(reviews_path.clone());
+        }
+        if let Some(reviewed_items) = matches.get_one::<String>("reviewed_items") {
+            self.reviewed_items = match reviewed_items.as_str() {
+                "keep" => ReviewedItems::Keep,
+                "exclude" => ReviewedItems::Exclude,
+                _ => ReviewedItems::Downrank,
+            };
+        }
+        if matches.get_flag("strict") {
+            self.strict = true;
+        }
+        if let Some(timezone) = matches.get_one::<String>("timezone") {
+            self.timezone = timezone.clone();
+        }
+        if let Some(format) = matches.get_one::<String>("datetime_format") {
+            self.datetime_format = format.clone();
+        }
+        if let Some(format) = matches.get_one::<String>("filename_datetime_format") {
+            self.filename_datetime_format = format.clone();
+        }
+        if let Some(columns) = matches.get_many::<String>("extra_columns") {
+            self.extra_columns = columns.cloned().collect();
+        }
+    }
+
+    /*  -----------------------------------------------------------------
+        Checks that the merged config is usable before any work starts.
+        -----------------------------------------------------------------
+    */
+    pub fn validate(&self) -> Result<(), TrackerError> {
+        if self.serve_csv_path.is_some() {
+            // -- nothing is scanned or written; only the address matters
+            if self.serve.is_none() {
+                return Err(TrackerError::Config(
+                    "`serve_csv_path` requires `serve`, the address to listen on".to_string(),
+                ));
+            }
+            return Ok(());
+        }
+        if self.serve.is_some() && self.dry_run {
+            return Err(TrackerError::Config(
+                "`serve` has no results to serve with `dry_run`".to_string(),
+            ));
+        }
+        if self.watch && (self.dry_run || self.serve.is_some()) {
+            return Err(TrackerError::Config(
+                "`watch` can't be combined with `dry_run` or `serve`".to_string(),
+            ));
+        }
+        if self.watch_debounce_seconds == 0 {
+            return Err(TrackerError::Config(
+                "`watch_debounce_seconds` must be at least 1".to_string(),
+            ));
+        }
+        if self.source_dir_path.is_none() {
+            return Err(TrackerError::Config(
+                "`source_dir_path` must be set via `--source_dir_path` or the config file".to_string(),
+            ));
+        }
+        if self.output_dir_path.is_none() && !self.dry_run {
+            return Err(TrackerError::Config(
+                "`output_dir_path` must be set via `--output_dir_path` or the config file".to_string(),
+            ));
+        }
+        if self.resume && self.checkpoint_every == 0 {
+            return Err(TrackerError::Config(
+                "`resume` requires checkpointing; `checkpoint_every` must be at least 1".to_string(),
+            ));
+        }
+        if self.threads == Some(0) {
+            return Err(TrackerError::Config("`threads` must be at least 1".to_string()));
+        }
+        if self.max_concurrent_reads == Some(0) {
+            return Err(TrackerError::Config(
+                "`max_concurrent_reads` must be at least 1".to_string(),
+            ));
+        }
+        if self.files_per_second.is_some_and(|rate| rate <= 0.0 || !rate.is_finite()) {
+            return Err(TrackerError::Config(
+                "`files_per_second` must be a positive number".to_string(),
+            ));
+        }
+        self.timezone()?;
+        if !is_valid_strftime(&self.datetime_format) {
+            return Err(TrackerError::Config(format!(
+                "`datetime_format` ``{}`` is not a valid strftime-format",
+                self.datetime_format
+            )));
+        }
+        if !is_valid_strftime(&self.filename_datetime_format) {
+            return Err(TrackerError::Config(format!(
+                "`filename_datetime_format` ``{}`` is not a valid strftime-format",
+                self.filename_datetime_format
+            )));
+        }
+        if self.filename_datetime_format.contains('/') {
+            return Err(TrackerError::Config(
+                "`filename_datetime_format` must not contain `/`".to_string(),
+            ));
+        }
+        if !self.output_filename_pattern.contains("{datetime}") {
+            return Err(TrackerError::Config(
+                "`output_filename_pattern` must contain `{datetime}`".to_string(),
+            ));
+        }
+        let fixed_columns: Vec<String> = helper::csv_columns(&[]);
+        for (i, column) in self.extra_columns.iter().enumerate() {
+            if column.is_empty() || fixed_columns.contains(column) || self.extra_columns[..i].contains(column)
+            {
+                return Err(TrackerError::Config(format!(
+                    "`extra_columns`: ``{}`` is empty, a built-in column, or listed twice",
+                    column
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /*  -----------------------------------------------------------------
+        Parses the `timezone` setting; "utc" is accepted in any case.
+        -----------------------------------------------------------------
+    */
+    pub fn timezone(&self) -> Result<Tz, TrackerError> {
+        if self.timezone.eq_ignore_ascii_case("utc") {
+            return Ok(Tz::UTC);
+        }
+        self.timezone.parse::<Tz>().map_err(|_| {
+            TrackerError::Config(format!(
+                "`timezone` ``{}`` is not a valid IANA timezone name",
+                self.timezone
+            ))
+        })
+    }
+}
+
+/// Whether `format` is a usable strftime-format; chrono panics when displaying an invalid one.
+pub fn is_valid_strftime(format: &str) -> bool {
+    !StrftimeItems::new(format).any(|item| matches!(item, Item::Error))
+}
@@ -0,0 +1,138 @@
+use crate::config::Config;
+use crate::helper::DiscoveredPaths;
+use crate::log_debug;
+use indexmap::IndexMap;
+use rayon::prelude::*;
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+/*  -----------------------------------------------------------------
+    Inventories the paths that find_json_files() gathered, without parsing any json or writing any csv.
+    Purpose: a quick sanity-check that a new mount really is the tree we expect before a multi-hour run.
+    Reports:
+    - counts (and bytes) per category
+    - counts per category per collection-directory (the first directory under `source_dir`, eg `HH001545`)
+    - counts per file-extension of the `other` paths
+    - total bytes
+    - the deepest and shallowest paths
+    - the paths the walk couldn't get into
+    -----------------------------------------------------------------
+*/
+pub fn prepare_inventory_json(
+    source_dir: &str, paths: &DiscoveredPaths, start_instant: Instant, formatted_date_time: String,
+    config: &Config,
+) -> String {
+    let source_root: &Path = Path::new(source_dir);
+    let other_paths: &[PathBuf] = &paths.other_paths;
+    let categories: [(&str, &[PathBuf]); 4] = [
+        ("ocr_tracker", &paths.ocr_tracker_paths),
+        ("ingest_tracker", &paths.ingest_tracker_paths),
+        ("error_tracker", &paths.error_tracker_paths),
+        ("other", other_paths),
+    ];
+
+    // -- counts and bytes per category
+    let mut category_counts = IndexMap::<String, Value>::new();
+    let mut category_bytes = IndexMap::<String, Value>::new();
+    let mut total_bytes: u64 = 0;
+    for (category, paths) in categories.iter() {
+        let bytes: u64 = paths.par_iter().map(|path| file_size(path)).sum();
+        total_bytes += bytes;
+        category_counts.insert(category.to_string(), json!(paths.len()));
+        category_bytes.insert(category.to_string(), json!(bytes));
+    }
+
+    // -- counts per collection-directory
+    let mut collection_counts: BTreeMap<String, IndexMap<String, usize>> = BTreeMap::new();
+    for (category, paths) in categories.iter() {
+        for path in paths.iter() {
+            let collection: String = collection_dir(source_root, path);
+            let counts = collection_counts.entry(collection).or_insert_with(|| {
+                categories.iter().map(|(c, _)| (c.to_string(), 0)).collect::<IndexMap<String, usize>>()
+            });
+            *counts.entry(category.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    // -- counts per extension of the `other` paths
+    let mut extension_counts: BTreeMap<String, usize> = BTreeMap::new();
+    for path in other_paths {
+        let extension: String = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_else(|| "(none)".to_string());
+        *extension_counts.entry(extension).or_insert(0) += 1;
+    }
+
+    // -- deepest and shallowest paths (depth is counted from `source_dir`)
+    let all_paths = categories.iter().flat_map(|(_, paths)| paths.iter());
+    let depths: Vec<(usize, &PathBuf)> =
+        all_paths.map(|path| (path_depth(source_root, path), path)).collect();
+    let deepest: Option<&(usize, &PathBuf)> = depths.iter().max_by_key(|(depth, _)| *depth);
+    let shallowest: Option<&(usize, &PathBuf)> = depths.iter().min_by_key(|(depth, _)| *depth);
+    log_debug!("deepest, ``{:?}``; shallowest, ``{:?}``", deepest, shallowest);
+
+    // -- build the json
+    let mut map = IndexMap::<String, Value>::new();
+    map.insert("datetime_stamp".to_string(), json!(formatted_date_time));
+    map.insert("time_taken".to_string(), json!("temp_holder")); // the same insert-key will update it later
+    map.insert(
+        "mode".to_string(),
+        json!("dry_run (no json parsed; no csv written)"),
+    );
+    map.insert("source_dir_path".to_string(), json!(source_dir));
+    map.insert("category_counts".to_string(), json!(category_counts));
+    map.insert("category_bytes".to_string(), json!(category_bytes));
+    map.insert("total_bytes".to_string(), json!(total_bytes));
+    map.insert("collection_count".to_string(), json!(collection_counts.len()));
+    map.insert("collection_counts".to_string(), json!(collection_counts));
+    map.insert("other_extension_counts".to_string(), json!(extension_counts));
+    map.insert("walk_errors".to_string(), json!(paths.walk_errors));
+    map.insert("deepest_path".to_string(), depth_json(deepest));
+    map.insert("shallowest_path".to_string(), depth_json(shallowest));
+    map.insert("config".to_string(), json!(config));
+
+    // -- finally, update elapsed time value (the key was created above)
+    let elapsed_seconds: f64 = start_instant.elapsed().as_secs_f64(); // uses monotonic clock
+    map.insert(
+        "time_taken".to_string(),
+        json!(format!("{:.1} seconds", elapsed_seconds)),
+    );
+
+    match serde_json::to_string_pretty(&map) {
+        Ok(json) => json,
+        Err(e) => format!("Error serializing dry-run JSON: {}", e),
+    }
+}
+
+// returns 0 if the file can't be stat-ed (eg, it was removed after the walk)
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+// `/source/HH001545/HH001545_0001/x.json` -> `HH001545`; files directly in `source_dir` go under "(root)"
+fn collection_dir(source_root: &Path, path: &Path) -> String {
+    let relative: &Path = path.strip_prefix(source_root).unwrap_or(path);
+    let mut components = relative.components();
+    match (components.next(), components.next()) {
+        (Some(first), Some(_)) => first.as_os_str().to_string_lossy().to_string(),
+        _ => "(root)".to_string(),
+    }
+}
+
+fn path_depth(source_root: &Path, path: &Path) -> usize {
+    path.strip_prefix(source_root).unwrap_or(path).components().count()
+}
+
+fn depth_json(entry: Option<&(usize, &PathBuf)>) -> Value {
+    match entry {
+        Some((depth, path)) => json!({ "depth": depth, "path": path.to_string_lossy() }),
+        None => Value::Null,
+    }
+}
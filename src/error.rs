@@ -0,0 +1,138 @@
+use serde_json::{json, Value};
+use std::{
+    error::Error,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+/*  -----------------------------------------------------------------
+    The crate-wide error type; one variant per failure-category, each with its own process exit-code.
+    - Per-file read/parse/mapping failures aren't fatal: the file is logged and skipped (an unparseable
+      ocr-tracker is counted as rejected), so those variants mostly show up in the logs.
+    - A failed run returns `"status": "failed"` and an `error` object in the output-json, via to_json().
+    -----------------------------------------------------------------
+*/
+#[derive(Debug)]
+pub enum TrackerError {
+    // bad config-file, cli-flag, or scanner-option
+    Config(String),
+    // the source-dir can't be walked
+    Discovery {
+        path: PathBuf,
+        source: io::Error,
+    },
+    // a tracker, checkpoint or config file can't be opened or read
+    Read {
+        path: PathBuf,
+        source: io::Error,
+    },
+    // a tracker isn't the json we expect
+    Parse {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    // an ingest-tracker's file-name doesn't give an item-key for the id-to-pid map
+    Mapping {
+        path: PathBuf,
+        message: String,
+    },
+    // the csv, checkpoint or log-file can't be written
+    Output {
+        path: PathBuf,
+        source: Box<dyn Error + Send + Sync>,
+    },
+}
+
+impl TrackerError {
+    /*  -----------------------------------------------------------------
+        Exit-codes, per category. (2 matches clap's exit-code for bad arguments;
+        130, for an interrupted run, is in shutdown.rs.)
+        -----------------------------------------------------------------
+    */
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            TrackerError::Config(_) => 2,
+            TrackerError::Discovery { .. } => 3,
+            TrackerError::Read { .. } => 4,
+            TrackerError::Parse { .. } => 5,
+            TrackerError::Mapping { .. } => 6,
+            TrackerError::Output { .. } => 7,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TrackerError::Config(_) => "config",
+            TrackerError::Discovery { .. } => "discovery",
+            TrackerError::Read { .. } => "read",
+            TrackerError::Parse { .. } => "parse",
+            TrackerError::Mapping { .. } => "mapping",
+            TrackerError::Output { .. } => "output",
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            TrackerError::Config(_) => None,
+            TrackerError::Discovery { path, .. }
+            | TrackerError::Read { path, .. }
+            | TrackerError::Parse { path, .. }
+            | TrackerError::Mapping { path, .. }
+            | TrackerError::Output { path, .. } => Some(path),
+        }
+    }
+
+    pub fn output(path: impl Into<PathBuf>, source: impl Into<Box<dyn Error + Send + Sync>>) -> TrackerError {
+        TrackerError::Output {
+            path: path.into(),
+            source: source.into(),
+        }
+    }
+
+    /*  -----------------------------------------------------------------
+        The `error` object for the output-json.
+        -----------------------------------------------------------------
+    */
+    pub fn to_json(&self) -> Value {
+        json!({
+            "kind": self.kind(),
+            "path": self.path().map(|path| path.to_string_lossy()),
+            "message": self.to_string(),
+            "exit_code": self.exit_code(),
+        })
+    }
+}
+
+impl fmt::Display for TrackerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrackerError::Config(message) => write!(f, "Invalid configuration: {}", message),
+            TrackerError::Discovery { path, source } => {
+                write!(f, "Failed to walk ``{}``: {}", path.display(), source)
+            }
+            TrackerError::Read { path, source } => {
+                write!(f, "Failed to read ``{}``: {}", path.display(), source)
+            }
+            TrackerError::Parse { path, source } => {
+                write!(f, "Failed to parse ``{}``: {}", path.display(), source)
+            }
+            TrackerError::Mapping { path, message } => {
+                write!(f, "No id-to-pid entry from ``{}``: {}", path.display(), message)
+            }
+            TrackerError::Output { path, source } => {
+                write!(f, "Failed to write ``{}``: {}", path.display(), source)
+            }
+        }
+    }
+}
+
+impl Error for TrackerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TrackerError::Config(_) | TrackerError::Mapping { .. } => None,
+            TrackerError::Discovery { source, .. } | TrackerError::Read { source, .. } => Some(source),
+            TrackerError::Parse { source, .. } => Some(source),
+            TrackerError::Output { source, .. } => Some(source.as_ref()),
+        }
+    }
+}
@@ -0,0 +1,421 @@
+/*  -----------------------------------------------------------------
+    Builds synthetic tracker-trees, shaped like the Hall-Hoag one, for tests and benchmarks:
+
+        HH001545/HH001545-ocr_complete.json                          (organization-file)
+        HH001545/HH001545_0001/HH001545_0001-ocr_complete.json       (ocr-tracker)
+        HH001545/HH001545_0001/HH001545_0001-ingest_complete.json    (ingest-tracker)
+        HH001545/HH001545_0001/HH001545_0001-ocr_error.json          (error-tracker)
+        HH001545/notes_1.txt                                         (junk)
+
+    The counts come from a FixtureSpec; which items get which files, and the ocr-stats, come from a
+    seeded generator, so the same spec always builds the same tree. generate() returns a FixtureManifest
+    of what a scan of the tree should find, for assertions.
+    The `generate_fixtures` binary wraps this; see `src/bin/generate_fixtures.rs`.
+    -----------------------------------------------------------------
+*/
+use crate::error::TrackerError;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const FIRST_COLLECTION_NUMBER: usize = 1545;
+const FIRST_PID_NUMBER: usize = 100_001;
+
+/*  -----------------------------------------------------------------
+    What to generate. All counts are totals for the whole tree.
+    - `items` is the number of item-dirs; each gets an ocr-tracker. They're spread evenly over the collections.
+    - `ingest_trackers` and `error_trackers` go in item-dirs, at most one of each per item.
+    - `organization_files` go in collection-dirs, at most one per collection; they're named like
+      ocr-trackers, so discovery counts them as ocr-trackers and process_files() rejects them.
+    - `dash_placeholders` ocr-trackers have `"-"` for the orientation- and script-fields, like the real
+      trackers for pages where orientation-detection failed; they still parse.
+    - `malformed_ocr_trackers` and `malformed_ingest_trackers` are truncated, empty, or wrongly-typed;
+      the former are rejected, the latter skipped (so those items get no pid).
+    -----------------------------------------------------------------
+*/
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FixtureSpec {
+    pub collections: usize,
+    pub items: usize,
+    pub ingest_trackers: usize,
+    pub error_trackers: usize,
+    pub organization_files: usize,
+    pub junk_files: usize,
+    pub dash_placeholders: usize,
+    pub malformed_ocr_trackers: usize,
+    pub malformed_ingest_trackers: usize,
+    pub seed: u64,
+}
+
+impl Default for FixtureSpec {
+    fn default() -> Self {
+        FixtureSpec::scaled(5, 100)
+    }
+}
+
+impl FixtureSpec {
+    /*  -----------------------------------------------------------------
+        Roughly the real tree's proportions: nearly every item ingested, an organization-file and a
+        junk-file per collection, and a few percent of error-trackers, placeholders and malformed files.
+        The shares round up, so even a small tree has one of each; they're clamped to what the items can hold.
+        -----------------------------------------------------------------
+    */
+    pub fn scaled(collections: usize, items: usize) -> FixtureSpec {
+        let share = |per_thousand: usize| (items * per_thousand).div_ceil(1000);
+        let ingest_trackers: usize = items - share(50);
+        let dash_placeholders: usize = share(30);
+        FixtureSpec {
+            collections,
+            items,
+            ingest_trackers,
+            error_trackers: share(10),
+            organization_files: collections,
+            junk_files: collections,
+            dash_placeholders,
+            malformed_ocr_trackers: share(5).min(items - dash_placeholders),
+            malformed_ingest_trackers: share(5).min(ingest_trackers),
+            seed: 1545,
+        }
+    }
+
+    fn validate(&self) -> Result<(), TrackerError> {
+        let problem: Option<&str> = if self.collections == 0 && self.items + self.junk_files > 0 {
+            Some("`collections` must be at least 1 to hold the items and junk-files")
+        } else if self.ingest_trackers > self.items {
+            Some("`ingest_trackers` can't be more than `items`")
+        } else if self.error_trackers > self.items {
+            Some("`error_trackers` can't be more than `items`")
+        } else if self.organization_files > self.collections {
+            Some("`organization_files` can't be more than `collections`")
+        } else if self.dash_placeholders + self.malformed_ocr_trackers > self.items {
+            Some("`dash_placeholders` plus `malformed_ocr_trackers` can't be more than `items`")
+        } else if self.malformed_ingest_trackers > self.ingest_trackers {
+            Some("`malformed_ingest_trackers` can't be more than `ingest_trackers`")
+        } else {
+            None
+        };
+        match problem {
+            Some(message) => Err(TrackerError::Config(message.to_string())),
+            None => Ok(()),
+        }
+    }
+}
+
+/*  -----------------------------------------------------------------
+    What a scan of the generated tree, with the default config, should find.
+    Paths are relative to the tree's root, with `/` separators, and sorted.
+    -----------------------------------------------------------------
+*/
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FixtureManifest {
+    pub spec: FixtureSpec,
+    pub ocr_tracker_count: usize,
+    pub ingest_tracker_count: usize,
+    pub error_tracker_count: usize,
+    pub other_count: usize,
+    pub pid_count: usize,
+    pub record_count: usize,
+    pub rejected_count: usize,
+    pub rejected_paths: Vec<String>,
+    pub skipped_ingest_tracker_paths: Vec<String>,
+    pub records: Vec<ExpectedRecord>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ExpectedRecord {
+    pub image_name: String,
+    pub pid: Option<String>,
+    pub has_placeholders: bool,
+}
+
+// how an item's ocr-tracker is written
+#[derive(Clone, Copy, PartialEq)]
+enum OcrKind {
+    Stats,
+    Placeholders,
+    Malformed,
+}
+
+/*  -----------------------------------------------------------------
+    Writes the tree under `root`, which must be missing or empty, and returns its manifest.
+    -----------------------------------------------------------------
+*/
+pub fn generate(root: &Path, spec: &FixtureSpec) -> Result<FixtureManifest, TrackerError> {
+    spec.validate()?;
+    let is_empty = match fs::read_dir(root) {
+        Ok(mut entries) => entries.next().is_none(),
+        Err(_) => true, // missing; created below
+    };
+    if !is_empty {
+        return Err(TrackerError::Config(format!(
+            "the fixture-dir ``{}`` isn't empty",
+            root.display()
+        )));
+    }
+
+    // -- decide which items get which files ------------------------
+    let mut rng = FixtureRng::new(spec.seed);
+    let mut ocr_kinds = vec![OcrKind::Stats; spec.items];
+    let order = rng.shuffled(spec.items);
+    for &i in &order[..spec.dash_placeholders] {
+        ocr_kinds[i] = OcrKind::Placeholders;
+    }
+    for &i in &order[spec.dash_placeholders..spec.dash_placeholders + spec.malformed_ocr_trackers] {
+        ocr_kinds[i] = OcrKind::Malformed;
+    }
+    let has_ingest = rng.chosen(spec.items, spec.ingest_trackers);
+    let ingest_items: Vec<usize> = (0..spec.items).filter(|&i| has_ingest[i]).collect();
+    let malformed_ingest = rng.chosen(ingest_items.len(), spec.malformed_ingest_trackers);
+    let has_error = rng.chosen(spec.items, spec.error_trackers);
+    let has_organization_file = rng.chosen(spec.collections, spec.organization_files);
+
+    // -- write the tree --------------------------------------------
+    let mut manifest = FixtureManifest {
+        spec: spec.clone(),
+        ocr_tracker_count: spec.items + spec.organization_files,
+        ingest_tracker_count: spec.ingest_trackers,
+        error_tracker_count: spec.error_trackers,
+        other_count: spec.junk_files,
+        pid_count: spec.ingest_trackers - spec.malformed_ingest_trackers,
+        record_count: spec.items - spec.malformed_ocr_trackers,
+        rejected_count: spec.organization_files + spec.malformed_ocr_trackers,
+        rejected_paths: Vec::new(),
+        skipped_ingest_tracker_paths: Vec::new(),
+        records: Vec::new(),
+    };
+    write_file(root, "", "")?; // creates the root, even for an empty spec
+    let mut item_index: usize = 0;
+    let mut ingest_index: usize = 0;
+    for (c, &has_organization_file) in has_organization_file.iter().enumerate() {
+        let collection = format!("HH{:06}", FIRST_COLLECTION_NUMBER + c);
+        if has_organization_file {
+            let relative_path = format!("{0}/{0}-ocr_complete.json", collection);
+            write_file(root, &relative_path, &organization_json(&collection))?;
+            manifest.rejected_paths.push(relative_path);
+        }
+        for n in 0..spread(spec.junk_files, spec.collections, c) {
+            let relative_path = format!("{}/{}", collection, junk_name(c * spec.junk_files + n));
+            write_file(root, &relative_path, "")?;
+        }
+
+        for n in 0..spread(spec.items, spec.collections, c) {
+            let item = format!("{}_{:04}", collection, n + 1);
+            let image_name = format!("{}.jp2", item);
+            let item_dir = format!("{}/{}", collection, item);
+
+            // -- the ingest-tracker; the pid, if it's usable
+            let mut pid: Option<String> = None;
+            if has_ingest[item_index] {
+                let relative_path = format!("{}/{}-ingest_complete.json", item_dir, item);
+                if malformed_ingest[ingest_index] {
+                    write_file(root, &relative_path, malformed_ingest_json(ingest_index))?;
+                    manifest.skipped_ingest_tracker_paths.push(relative_path);
+                } else {
+                    let item_pid = format!("bdr:{}", FIRST_PID_NUMBER + item_index);
+                    write_file(root, &relative_path, &json!({ "pid": item_pid }).to_string())?;
+                    pid = Some(item_pid);
+                }
+                ingest_index += 1;
+            }
+
+            // -- the error-tracker
+            if has_error[item_index] {
+                let relative_path = format!("{}/{}-ocr_error.json", item_dir, item);
+                let error_json = json!({ "image_name": image_name, "error": "tesseract timed out" });
+                write_file(root, &relative_path, &error_json.to_string())?;
+            }
+
+            // -- the ocr-tracker
+            let relative_path = format!("{}/{}-ocr_complete.json", item_dir, item);
+            let kind = ocr_kinds[item_index];
+            match kind {
+                OcrKind::Malformed => {
+                    write_file(root, &relative_path, &malformed_ocr_json(&image_name, item_index))?;
+                    manifest.rejected_paths.push(relative_path);
+                }
+                OcrKind::Stats | OcrKind::Placeholders => {
+                    let ocr_json = ocr_json(&mut rng, &image_name, kind == OcrKind::Placeholders);
+                    write_file(root, &relative_path, &ocr_json.to_string())?;
+                    manifest.records.push(ExpectedRecord {
+                        image_name,
+                        pid,
+                        has_placeholders: kind == OcrKind::Placeholders,
+                    });
+                }
+            }
+            item_index += 1;
+        }
+    }
+
+    manifest.rejected_paths.sort();
+    manifest.skipped_ingest_tracker_paths.sort();
+    manifest.records.sort_by(|a, b| a.image_name.cmp(&b.image_name));
+    Ok(manifest)
+}
+
+// writes `contents` to `root/relative_path`, creating the parent-dirs; an empty path just creates `root`
+fn write_file(root: &Path, relative_path: &str, contents: &str) -> Result<(), TrackerError> {
+    if relative_path.is_empty() {
+        return fs::create_dir_all(root).map_err(|e| TrackerError::output(root, e));
+    }
+    let path: PathBuf = root.join(relative_path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| TrackerError::output(parent, e))?;
+    }
+    fs::write(&path, contents).map_err(|e| TrackerError::output(&path, e))
+}
+
+// how many of `total` things go in the `index`th of `buckets`, spreading the remainder over the first ones
+fn spread(total: usize, buckets: usize, index: usize) -> usize {
+    total / buckets + usize::from(index < total % buckets)
+}
+
+/*  -----------------------------------------------------------------
+    The ocr-stats. Roughly the real distributions:
+    - about 1 page in 12 is blank (no words, and zero confidence-stats).
+    - word-counts are skewed toward short pages; confidences cluster in the high 80s and low 90s.
+    - the below-90/60/30 percentages shrink in turn, and rise as the average confidence falls.
+    - most pages are upright and Latin-script.
+    With `placeholders`, the orientation- and script-fields are `"-"`, as when detection failed.
+    -----------------------------------------------------------------
+*/
+fn ocr_json(rng: &mut FixtureRng, image_name: &str, placeholders: bool) -> Value {
+    let is_blank = rng.unit() < 1.0 / 12.0;
+    let (word_count, avg_confidence, below_90, below_60, below_30) = if is_blank {
+        (0, 0.0, 0.0, 0.0, 0.0)
+    } else {
+        let word_count = 1 + (rng.unit().powi(2) * 1200.0) as i32;
+        let spread = (rng.unit() + rng.unit() + rng.unit()) / 3.0 - 0.5; // roughly normal, in -0.5..0.5
+        let avg_confidence = (89.0 + spread * 30.0).clamp(20.0, 97.0);
+        let below_90 = ((100.0 - avg_confidence) * (1.5 + rng.unit())).min(100.0);
+        let below_60 = below_90 * (0.3 + rng.unit() * 0.3);
+        let below_30 = below_60 * (0.2 + rng.unit() * 0.3);
+        (word_count, avg_confidence, below_90, below_60, below_30)
+    };
+    let (orientation, orientation_conf, script, script_conf): (Value, Value, Value, Value) = if placeholders {
+        (json!("-"), json!("-"), json!("-"), json!("-"))
+    } else {
+        let orientation = match rng.unit() {
+            u if u < 0.9 => 0,
+            u if u < 0.95 => 180,
+            u if u < 0.98 => 90,
+            _ => 270,
+        };
+        let script = match rng.unit() {
+            u if u < 0.92 => "Latin",
+            u if u < 0.96 => "Cyrillic",
+            u if u < 0.98 => "Greek",
+            _ => "Han",
+        };
+        (
+            json!(orientation),
+            json!(round2(0.5 + rng.unit() * 25.0)),
+            json!(script),
+            json!(round2(0.5 + rng.unit() * 12.0)),
+        )
+    };
+    json!({
+        "orientation": orientation,
+        "orientation_conf": orientation_conf,
+        "script": script,
+        "script_conf": script_conf,
+        "image_name": image_name,
+        "word_count": word_count,
+        "avg_confidence": round2(avg_confidence),
+        "below_90": round2(below_90),
+        "below_60": round2(below_60),
+        "below_30": round2(below_30),
+    })
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+// collection-level tracker; lists the collection's items rather than having ocr-stats
+fn organization_json(collection: &str) -> String {
+    json!({ "collection": collection, "items": [] }).to_string()
+}
+
+// cycles through the ways a real tracker goes bad: truncated, empty, and a dash where none is allowed
+fn malformed_ocr_json(image_name: &str, index: usize) -> String {
+    match index % 3 {
+        0 => format!(
+            r#"{{"orientation": 0, "orientation_conf": 3.1, "image_name": "{}", "word_c"#,
+            image_name
+        ),
+        1 => String::new(),
+        _ => format!(
+            r#"{{"orientation": 0, "orientation_conf": 3.1, "script": "Latin", "script_conf": 2.0, "image_name": "{}", "word_count": "-", "avg_confidence": 0.0, "below_90": 0.0, "below_60": 0.0, "below_30": 0.0}}"#,
+            image_name
+        ),
+    }
+}
+
+// truncated, empty, and a non-string pid
+fn malformed_ingest_json(index: usize) -> &'static str {
+    match index % 3 {
+        0 => r#"{"pid": "bdr:"#,
+        1 => "",
+        _ => r#"{"pid": 1545}"#,
+    }
+}
+
+fn junk_name(index: usize) -> String {
+    match index % 4 {
+        0 => format!("notes_{}.txt", index),
+        1 => format!("checksums_{}.md5", index),
+        2 => format!("scan_{}.tif", index),
+        _ => format!(".DS_Store_{}", index),
+    }
+}
+
+/*  -----------------------------------------------------------------
+    A small seeded generator (splitmix64), so the fixtures don't need a `rand` dependency,
+    and so a given seed builds the same tree on every platform.
+    -----------------------------------------------------------------
+*/
+struct FixtureRng {
+    state: u64,
+}
+
+impl FixtureRng {
+    fn new(seed: u64) -> FixtureRng {
+        FixtureRng { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // in 0.0..1.0
+    fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // 0..n, in a random order
+    fn shuffled(&mut self, n: usize) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..n).collect();
+        for i in (1..n).rev() {
+            let j = (self.next_u64() % (i as u64 + 1)) as usize;
+            order.swap(i, j);
+        }
+        order
+    }
+
+    // a mask of `n` flags, `k` of them set
+    fn chosen(&mut self, n: usize, k: usize) -> Vec<bool> {
+        let mut mask = vec![false; n];
+        for &i in &self.shuffled(n)[..k] {
+            mask[i] = true;
+        }
+        mask
+    }
+}
@@ -1,67 +1,82 @@
-use crate::{log_debug, log_info}; // requires `logger` to be declared as `pub mod logger;` in `main.rs
+use crate::checkpoint::Checkpoint;
+use crate::config::Config;
+use crate::error::TrackerError;
+use crate::scanner::ScanSummary;
+use crate::schema::{self, SchemaVersion};
+use crate::shutdown;
+use crate::throttle::IoThrottle;
+use crate::validation::{self, ValidationWarning};
+use crate::{log_debug, log_info, log_trace, log_warn}; // requires `logger` to be declared as `pub mod logger;` in `lib.rs`
+use chrono::DateTime;
+use chrono_tz::Tz;
+use ignore::{DirEntry, ParallelVisitor, ParallelVisitorBuilder, WalkBuilder, WalkState};
 use indexmap::IndexMap;
 use rayon::prelude::*;
-use serde::de::Deserializer;
+use serde::de::{self, Deserializer, Unexpected};
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Result as JsonResult, Value};
+use serde_json::{json, Map, Value};
 use std::{
+    cell::RefCell,
     collections::BTreeMap,
-    fs::File,
-    io::Read,
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, Read, Write},
     path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
     time::Instant,
 };
-use walkdir::WalkDir;
 
 /*  -----------------------------------------------------------------
     Represents the structure of the -->OCR<-- JSON tracker files being parsed.
     Note that the `pid` and `pid_url` fields are not part of the original JSON files; they're populated later.
+    The fields are public so other tools using the library can read them; the order is the csv column-order.
+    Any fields the ocr-pipeline adds (eg engine-version, dpi) are kept in `extra`; they only get csv-columns
+    when listed in the config's `extra_columns`. One named like a Record field is kept as `ocr_(name)`.
+    Trackers are parsed via schema.rs, which normalizes each tracker-version into this one shape.
     -----------------------------------------------------------------
 */
 
-// needed to handle the fact that what used to be integers are now sometimes dashes
-fn deserialize_i32_or_dash<'de, D>(deserializer: D) -> Result<i32, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let v = Value::deserialize(deserializer)?;
-    if v == "-" {
-        Ok(0) // Or any default value you prefer
-    } else {
-        v.as_i64().map(|i| i as i32).ok_or_else(|| serde::de::Error::custom("expected an integer or '-'"))
-    }
-}
-
-// needed to handle the fact that what used to be floats are now sometimes dashes
-fn deserialize_f64_or_dash<'de, D>(deserializer: D) -> Result<f64, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let v = Value::deserialize(deserializer)?;
-    if v == "-" {
-        Ok(0.0) // Or any default value you prefer
-    } else {
-        v.as_f64().ok_or_else(|| serde::de::Error::custom("expected a float or '-'"))
-    }
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Record {
+    pub orientation: i32,
+    pub orientation_conf: f64,
+    pub script: String,
+    pub script_conf: f64,
+    pub image_name: String,
+    pub word_count: i32,
+    pub avg_confidence: f64,
+    pub below_90: f64,
+    pub below_60: f64,
+    pub below_30: f64,
+    pub pid: Option<String>,           // populated later
+    pub pid_url: Option<String>,       // populated later
+    pub pid_source: Option<PidSource>, // populated later
+    // -- from the item's ingest-tracker (see IngestInfo); populated later
+    pub ingest_timestamp: Option<String>,
+    pub ingest_batch_id: Option<String>,
+    pub ingest_collection_id: Option<String>,
+    pub ingest_parent_pid: Option<String>,
+    pub ingest_extra: Option<String>, // the ingest-tracker's other fields, as a json-object
+    // -- from the reviews-csv (see reviews.rs); populated later, and only with `reviews_path`
+    pub review_status: Option<String>,
+    pub reviewer: Option<String>,
+    pub review_date: Option<String>,
+    pub schema_version: Option<SchemaVersion>, // the ocr-tracker's, declared or detected
+    #[serde(default, with = "validation::warning_codes")]
+    pub validation_warnings: Vec<ValidationWarning>, // see validation.rs; populated later
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>, // the ocr-tracker's other fields
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct Record {
-    #[serde(deserialize_with = "deserialize_i32_or_dash")]
-    orientation: i32,
-    #[serde(deserialize_with = "deserialize_f64_or_dash")]
-    orientation_conf: f64,
-    script: String,
-    #[serde(deserialize_with = "deserialize_f64_or_dash")]
-    script_conf: f64,
-    image_name: String,
-    word_count: i32,
-    avg_confidence: f64,
-    below_90: f64,
-    below_60: f64,
-    below_30: f64,
-    pid: Option<String>,     // populated later
-    pid_url: Option<String>, // populated later
+/*  -----------------------------------------------------------------
+    Where a record's pid came from: its ingest-tracker, or the pid-resolver (see resolver.rs).
+    -----------------------------------------------------------------
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PidSource {
+    Tracker,
+    Resolver,
 }
 
 // #[derive(Debug, Deserialize, Serialize)]
@@ -81,101 +96,345 @@ pub struct Record {
 // }
 
 /*  -----------------------------------------------------------------
-    Represents the structure of the -->ingestion<-- JSON tracker files that just have a pid.
-    The `id` field will be populated by parsing the local-id from the filepath.
+    Represents the structure of the -->ingestion<-- JSON tracker files.
+    Only the `pid` is required; the item's key comes from the file-path, not the json.
+    Owned, rather than borrowed from the read-buffer: the flattened `info` makes serde buffer the fields anyway.
     -----------------------------------------------------------------
 */
-#[derive(Debug, Deserialize, Serialize)]
-struct IdToPidInfo {
-    id: Option<String>, // populated later
+#[derive(Debug, Deserialize)]
+struct IngestTracker {
     pid: String,
+    #[serde(flatten)]
+    info: IngestInfo,
+}
+
+/*  -----------------------------------------------------------------
+    The ingest-tracker's fields besides the pid, for correlating ocr-quality with ingest batch and date.
+    - The known fields accept a few spellings (see the aliases), and either strings or numbers.
+    - Every other field is kept, as-is, in `extra`.
+    Joined onto each record as the `ingest_*` columns.
+    -----------------------------------------------------------------
+*/
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct IngestInfo {
+    #[serde(
+        default,
+        alias = "ingest_date",
+        alias = "ingested",
+        alias = "timestamp",
+        deserialize_with = "deserialize_optional_text"
+    )]
+    pub ingest_timestamp: Option<String>,
+    #[serde(
+        default,
+        alias = "batch",
+        alias = "ingest_batch",
+        deserialize_with = "deserialize_optional_text"
+    )]
+    pub batch_id: Option<String>,
+    #[serde(
+        default,
+        alias = "collection",
+        alias = "collection_pid",
+        deserialize_with = "deserialize_optional_text"
+    )]
+    pub collection_id: Option<String>,
+    #[serde(
+        default,
+        alias = "parent",
+        alias = "parent_pid_identifier",
+        deserialize_with = "deserialize_optional_text"
+    )]
+    pub parent_pid: Option<String>,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+// a string or a number (eg an epoch-timestamp, or a numeric batch-id), as text; null or blank is None
+fn deserialize_optional_text<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<Value>::deserialize(deserializer)? {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(text)) if text.trim().is_empty() => Ok(None),
+        Some(Value::String(text)) => Ok(Some(text)),
+        Some(Value::Number(number)) => Ok(Some(number.to_string())),
+        Some(other) => Err(de::Error::invalid_type(
+            Unexpected::Other(&other.to_string()),
+            &"a string or a number",
+        )),
+    }
+}
+
+/// What the ingest-trackers hold: each item's pid, plus its other ingest-fields (when it has any).
+#[derive(Clone, Debug, Default)]
+pub struct IngestTrackers {
+    pub id_to_pid_map: BTreeMap<String, String>,
+    pub ingest_info_map: BTreeMap<String, IngestInfo>,
+}
+
+/*  -----------------------------------------------------------------
+    Holds the paths find_json_files() gathered, by category; each vector is sorted.
+    -----------------------------------------------------------------
+*/
+#[derive(Clone, Debug, Default)]
+pub struct DiscoveredPaths {
+    pub ocr_tracker_paths: Vec<PathBuf>,
+    pub ingest_tracker_paths: Vec<PathBuf>,
+    pub error_tracker_paths: Vec<PathBuf>, // file-names containing the error-marker
+    pub other_paths: Vec<PathBuf>,
+    pub walk_errors: Vec<WalkError>, // eg permission-denied dirs, broken symlinks, symlink-loops
+}
+
+/*  -----------------------------------------------------------------
+    A path the walk couldn't get into, or past; reported rather than skipped silently.
+    -----------------------------------------------------------------
+*/
+#[derive(Clone, Debug, Serialize)]
+pub struct WalkError {
+    pub path: Option<PathBuf>,
+    pub message: String,
+}
+
+impl DiscoveredPaths {
+    // classifies one file by its name; the suffixes and error-marker come from the config
+    pub(crate) fn add_file(&mut self, path: PathBuf, config: &Config) {
+        let file_name: Option<&str> = path.file_name().and_then(|n| n.to_str());
+        let category: &mut Vec<PathBuf> = match file_name {
+            Some(n) if n.ends_with(config.ocr_tracker_suffix.as_str()) => &mut self.ocr_tracker_paths,
+            Some(n) if n.ends_with(config.ingest_tracker_suffix.as_str()) => &mut self.ingest_tracker_paths,
+            Some(n) if n.contains(config.error_tracker_marker.as_str()) => &mut self.error_tracker_paths,
+            _ => &mut self.other_paths,
+        };
+        category.push(path);
+    }
+
+    fn append(&mut self, other: &mut DiscoveredPaths) {
+        self.ocr_tracker_paths.append(&mut other.ocr_tracker_paths);
+        self.ingest_tracker_paths.append(&mut other.ingest_tracker_paths);
+        self.error_tracker_paths.append(&mut other.error_tracker_paths);
+        self.other_paths.append(&mut other.other_paths);
+        self.walk_errors.append(&mut other.walk_errors);
+    }
 }
 
 /*  -----------------------------------------------------------------
     Finds all files in the given directory that end with "ocr_complete.json" or "ingest_complete.json".
+    (The suffixes, and the "error" file-name marker, come from the config.)
+    - Walks in parallel (the `ignore` crate's walker, with all its ignore-file filtering turned off),
+      one walker-thread per rayon thread, since on NFS the walk is the slowest stage.
+    - Each entry is classified as it's visited, using the file-type the directory-listing already gave,
+      so there's no extra stat per file.
+    - Symlinks are followed; a broken one, a symlink-loop, or an unreadable directory is a walk-error.
     -----------------------------------------------------------------
 */
-pub fn find_json_files<P: AsRef<Path>>(path: P) -> (Vec<PathBuf>, Vec<PathBuf>, Vec<PathBuf>, Vec<PathBuf>) {
+pub fn find_json_files<P: AsRef<Path>>(path: P, config: &Config) -> DiscoveredPaths {
     log_debug!("starting find_json_files()");
+    let discovered: Mutex<DiscoveredPaths> = Mutex::new(DiscoveredPaths::default());
+    WalkBuilder::new(path)
+        .standard_filters(false) // no .gitignore, hidden-file, etc. filtering
+        .follow_links(true)
+        .threads(rayon::current_num_threads())
+        .build_parallel()
+        .visit(&mut ClassifierBuilder {
+            config,
+            discovered: &discovered,
+        });
+    let mut discovered: DiscoveredPaths = discovered.into_inner().unwrap();
+
+    // the walk's order isn't deterministic, so sort
+    discovered.ocr_tracker_paths.par_sort_unstable();
+    discovered.ingest_tracker_paths.par_sort_unstable();
+    discovered.error_tracker_paths.par_sort_unstable();
+    discovered.other_paths.par_sort_unstable();
+    discovered.walk_errors.sort_by(|a, b| a.path.cmp(&b.path));
+
+    log_info!("len-ocr_complete_paths: {}", discovered.ocr_tracker_paths.len());
+    log_info!(
+        "len-ingest_complete_paths: {}",
+        discovered.ingest_tracker_paths.len()
+    );
+    log_info!("len-error_paths: {}", discovered.error_tracker_paths.len());
+    log_info!("len-other_paths: {}", discovered.other_paths.len());
+    for walk_error in &discovered.walk_errors {
+        log_warn!(
+            error = walk_error.message.as_str(); // the message includes the path
+            "couldn't walk a path; anything under it is missing from the results"
+        );
+    }
+    discovered
+}
 
-    let entries: Vec<PathBuf> = WalkDir::new(path)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().is_file())
-        .map(|e| e.into_path())
-        .collect();
+// each walker-thread classifies into its own DiscoveredPaths, and merges it in when the thread finishes
+struct ClassifierBuilder<'a> {
+    config: &'a Config,
+    discovered: &'a Mutex<DiscoveredPaths>,
+}
 
-    // Use into_par_iter to consume entries and yield owned PathBufs
-    let (mut ocr_complete_paths, others): (Vec<PathBuf>, Vec<PathBuf>) =
-        entries.into_par_iter().partition(|path| {
-            path.file_name().and_then(|n| n.to_str()).map_or(false, |n| n.ends_with("ocr_complete.json"))
-        });
+struct Classifier<'a> {
+    config: &'a Config,
+    local: DiscoveredPaths,
+    discovered: &'a Mutex<DiscoveredPaths>,
+}
 
-    let (mut ingest_complete_paths, remaining): (Vec<PathBuf>, Vec<PathBuf>) =
-        others.into_par_iter().partition(|path| {
-            path.file_name().and_then(|n| n.to_str()).map_or(false, |n| n.ends_with("ingest_complete.json"))
-        });
+impl<'s> ParallelVisitorBuilder<'s> for ClassifierBuilder<'s> {
+    fn build(&mut self) -> Box<dyn ParallelVisitor + 's> {
+        Box::new(Classifier {
+            config: self.config,
+            local: DiscoveredPaths::default(),
+            discovered: self.discovered,
+        })
+    }
+}
 
-    let (mut error_paths, mut other_paths): (Vec<PathBuf>, Vec<PathBuf>) = remaining
-        .into_par_iter()
-        .partition(|path| path.file_name().and_then(|n| n.to_str()).map_or(false, |n| n.contains("error")));
-
-    // Optionally, sort the paths; note sorting is not parallelized
-    ocr_complete_paths.par_sort_unstable();
-    ingest_complete_paths.par_sort_unstable();
-    error_paths.par_sort_unstable();
-    other_paths.par_sort_unstable();
-
-    log_info!("len-ocr_complete_paths: {}", ocr_complete_paths.len());
-    log_info!("len-ingest_complete_paths: {}", ingest_complete_paths.len());
-    log_info!("len-error_paths: {}", error_paths.len());
-    log_info!("len-other_paths: {}", other_paths.len());
-
-    // return the four vectors
-    (
-        ocr_complete_paths,
-        ingest_complete_paths,
-        error_paths,
-        other_paths,
-    )
+impl ParallelVisitor for Classifier<'_> {
+    fn visit(&mut self, entry: Result<DirEntry, ignore::Error>) -> WalkState {
+        match entry {
+            Ok(entry) => {
+                if entry.file_type().is_some_and(|t| t.is_file()) {
+                    self.local.add_file(entry.into_path(), self.config);
+                }
+            }
+            Err(e) => self.local.walk_errors.push(WalkError {
+                path: walk_error_path(&e),
+                message: e.to_string(),
+            }),
+        }
+        if shutdown::requested() {
+            return WalkState::Quit;
+        }
+        WalkState::Continue
+    }
+}
+
+impl Drop for Classifier<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut discovered) = self.discovered.lock() {
+            discovered.append(&mut self.local);
+        }
+    }
+}
+
+fn walk_error_path(error: &ignore::Error) -> Option<PathBuf> {
+    match error {
+        ignore::Error::WithPath { path, .. } => Some(path.clone()),
+        ignore::Error::Loop { child, .. } => Some(child.clone()),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            walk_error_path(err)
+        }
+        ignore::Error::Partial(errors) => errors.iter().find_map(walk_error_path),
+        _ => None,
+    }
 }
 
 /*  -----------------------------------------------------------------
     Creates a hashmap of id-to-pid.
     (Ok ok, it's a BTreeMap, not a hashmap, cuz I wanted it sorted.)
+    Just the pids; read_ingest_trackers() also keeps the other ingest-fields.
     -----------------------------------------------------------------
 */
-pub fn make_id_to_pid_map(file_paths: Vec<PathBuf>) -> BTreeMap<String, String> {
-    let id_to_pid_map: BTreeMap<String, String> = file_paths
+pub fn make_id_to_pid_map(file_paths: Vec<PathBuf>, throttle: &IoThrottle) -> BTreeMap<String, String> {
+    read_ingest_trackers(&file_paths, throttle).id_to_pid_map
+}
+
+/*  -----------------------------------------------------------------
+    Reads the ingest-trackers into the id-to-pid map, and a map of each item's other ingest-fields.
+    A tracker that can't be read or parsed, or whose file-name gives no item-key, is skipped (and logged at debug).
+    -----------------------------------------------------------------
+*/
+pub fn read_ingest_trackers(file_paths: &[PathBuf], throttle: &IoThrottle) -> IngestTrackers {
+    let entries: Vec<(String, String, IngestInfo)> = file_paths
         .par_iter() // uses rayon's parallel iterator for performance
         .filter_map(|path_buf| {
             let path = path_buf.as_path();
-            let key = parse_key_from_path(&path);
-            let mut file = match File::open(&path) {
-                Ok(file) => file,
-                Err(e) => {
-                    log_debug!("Error opening file {:?}: {}", path, e);
-                    return None;
-                }
-            };
-            let mut contents = String::new();
-            if let Err(e) = file.read_to_string(&mut contents) {
-                log_debug!("Error reading file to string {:?}: {}", path, e);
-                return None;
-            }
-            let record: JsonResult<IdToPidInfo> = serde_json::from_str(&contents);
-            match record {
-                Ok(rec) => Some((key, rec.pid)),
+            let entry = ingest_tracker_key(path).and_then(|key| {
+                let (pid, info) = read_ingest_tracker(path, throttle)?;
+                Ok((key, pid, info))
+            });
+            match entry {
+                Ok(entry) => Some(entry),
                 Err(e) => {
-                    log_debug!("Error parsing JSON from {:?}: {}", path, e);
+                    log_debug!(
+                        tracker_path:% = path.display(), error_kind = e.kind(), error:% = e;
+                        "skipping ingest-tracker"
+                    );
                     None
                 }
             }
         })
         .collect();
 
-    log_debug!("id_to_pid_map, ``{:#?}``", id_to_pid_map);
-    id_to_pid_map
+    let mut ingest_trackers = IngestTrackers::default();
+    for (key, pid, info) in entries {
+        if info != IngestInfo::default() {
+            ingest_trackers.ingest_info_map.insert(key.clone(), info);
+        }
+        ingest_trackers.id_to_pid_map.insert(key, pid);
+    }
+    log_trace!("id_to_pid_map, ``{:#?}``", ingest_trackers.id_to_pid_map);
+    ingest_trackers
+}
+
+// the item-key for an ingest-tracker; one named just `-ingest_complete.json` (say) has none, and would otherwise map a stray pid
+fn ingest_tracker_key(path: &Path) -> Result<String, TrackerError> {
+    let key = parse_key_from_path(path);
+    if key.is_empty() || key == "unknown_key" {
+        return Err(TrackerError::Mapping {
+            path: path.to_path_buf(),
+            message: "no item-key can be parsed from the file-name".to_string(),
+        });
+    }
+    Ok(key)
+}
+
+// returns the pid, and the other fields, from one ingest-tracker file
+fn read_ingest_tracker(path: &Path, throttle: &IoThrottle) -> Result<(String, IngestInfo), TrackerError> {
+    with_tracker_bytes(path, throttle, |bytes| {
+        let record: IngestTracker = serde_json::from_slice(bytes).map_err(|e| TrackerError::Parse {
+            path: path.to_path_buf(),
+            source: e,
+        })?;
+        Ok((record.pid, record.info))
+    })
+}
+
+// one read-buffer per rayon worker; see with_tracker_bytes()
+thread_local! {
+    static READ_BUFFER: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+const KEPT_BUFFER_CAPACITY: usize = 1024 * 1024; // a stray huge file shouldn't pin its buffer
+
+/*  -----------------------------------------------------------------
+    Reads a whole tracker file, inside a throttle read-permit, into this thread's reusable buffer,
+    and hands the bytes to `parse`.
+    Purpose: with 1.6M+ small files, a fresh String per file was most of the
+    allocation-churn; now each rayon worker reuses one buffer, and serde_json parses the bytes directly.
+    `parse` must not call back into this function (the buffer is borrowed while it runs).
+    -----------------------------------------------------------------
+*/
+fn with_tracker_bytes<T>(
+    path: &Path, throttle: &IoThrottle, parse: impl FnOnce(&[u8]) -> Result<T, TrackerError>,
+) -> Result<T, TrackerError> {
+    READ_BUFFER.with(|buffer| {
+        let mut buffer = buffer.borrow_mut();
+        buffer.clear();
+        {
+            let _read_permit = throttle.acquire(); // released at the end of this block, after the read
+            let read_error = |e| TrackerError::Read {
+                path: path.to_path_buf(),
+                source: e,
+            };
+            let mut file = File::open(path).map_err(read_error)?;
+            file.read_to_end(&mut buffer).map_err(read_error)?;
+        }
+        let result = parse(&buffer);
+        if buffer.capacity() > KEPT_BUFFER_CAPACITY {
+            buffer.clear();
+            buffer.shrink_to(KEPT_BUFFER_CAPACITY);
+        }
+        result
+    })
 }
 
 /*  -----------------------------------------------------------------
@@ -189,11 +448,10 @@ pub fn parse_key_from_path(path: &Path) -> String {
     let key = path
         .file_stem() // Get the file stem from the path
         .and_then(|s| s.to_str()) // Convert OsStr to &str
-        .map(|s| s.split('-').next()) // Split at '-' and take the first part
-        .flatten() // Option<&str> from Option<Option<&str>>
+        .and_then(|s| s.split('-').next()) // Split at '-' and take the first part
         .map(|s| s.to_string()) // Convert &str to String
         .unwrap_or_else(|| "unknown_key".to_string()); // Provide default value on error
-    log_debug!("key, ``{}``", key);
+    log_trace!(tracker_path:% = path.display(), item_key = key.as_str(); "key parsed from path");
     key
 }
 
@@ -201,142 +459,408 @@ pub fn parse_key_from_path(path: &Path) -> String {
     Processes the JSON files
     - creates the data-vector that'll be used to create the CSV.
     - creates the vector of rejected paths -- should be just organization-tracker-files.
-    - assigns these two vectors to a PathResults struct and returns it in a Result.
+    - assigns these two vectors to a PathResults struct and returns it.
+    - a file that can't be read or parsed is logged and rejected; it doesn't fail the run.
     - uses parallel iteration to process the files concurrently for improved performance.
+    - stops dispatching new files once a Ctrl-C / SIGTERM is received; those are counted as unprocessed.
+    - with a checkpoint, reuses results from an earlier run and saves each new result as it goes.
     -----------------------------------------------------------------
 */
 pub struct PathResults {
-    // just a struct to hold and return the two vectors below
+    // just a struct to hold and return the vectors below
     pub extracted_data_files: Vec<Record>,
+    pub record_paths: Vec<PathBuf>, // each record's ocr-tracker, in the same order
     pub rejected_paths: Vec<PathBuf>,
+    pub unprocessed_count: usize, // non-zero only if the run was interrupted
+    pub resumed_count: usize,     // results reused from a checkpoint
 }
 
 pub fn process_files(
     ocr_tracker_filepaths: Vec<PathBuf>, id_to_pid_map: &BTreeMap<String, String>,
-) -> Result<PathResults, std::io::Error> {
-    let results: Vec<Result<Record, PathBuf>> = ocr_tracker_filepaths
+    resolved_pid_map: &BTreeMap<String, String>, pid_url_base: &str, throttle: &IoThrottle,
+    checkpoint: Option<&Checkpoint>,
+) -> PathResults {
+    let results: Vec<(&PathBuf, Result<Record, PathBuf>)> = ocr_tracker_filepaths
         .par_iter() // uses parallel iterator
-        .map(|ocr_tracker_filepath_buf| {
-            let ocr_tracker_filepath: &Path = ocr_tracker_filepath_buf.as_path(); // Path is simpler to work with than PathBuf
-            let item_num_key: String = parse_key_from_path(&ocr_tracker_filepath);
-
-            // Read OCR tracker file contents
-            match File::open(&ocr_tracker_filepath) {
-                Ok(mut file) => {
-                    let mut contents = String::new();
-                    if file.read_to_string(&mut contents).is_err() {
-                        return Err(ocr_tracker_filepath_buf.clone());
-                    }
-
-                    // Parse JSON contents to Record ----------------
-                    match serde_json::from_str::<Record>(&contents) {
-                        Ok(mut rec) => {
-                            // Look up PID and URL from hashmap
-                            let pid = id_to_pid_map.get(&item_num_key).cloned();
-                            let url = pid
-                                .as_ref()
-                                .map(|p| format!(" https://repository.library.brown.edu/studio/item/{}/", p));
-                            rec.pid = pid;
-                            rec.pid_url = url;
-
-                            Ok(rec)
-                        }
-                        Err(e) => {
-                            log_debug!(
-                                "error parsing ocr-json from ``{:?}``: ``{}`` -- likely an organization-file",
-                                ocr_tracker_filepath,
-                                e
-                            );
-                            Err(ocr_tracker_filepath_buf.clone())
-                        }
-                    }
+        .filter_map(|ocr_tracker_filepath_buf| {
+            if let Some(mut previous) = checkpoint.and_then(|c| c.previous_result(ocr_tracker_filepath_buf)) {
+                // -- already processed by an earlier, resumed run; its pid may have changed since
+                if let Ok(rec) = &mut previous {
+                    let item_num_key: String = parse_key_from_path(ocr_tracker_filepath_buf);
+                    join_pid(rec, &item_num_key, id_to_pid_map, resolved_pid_map, pid_url_base);
                 }
-                Err(_) => Err(ocr_tracker_filepath_buf.clone()),
+                return Some((ocr_tracker_filepath_buf, previous));
+            }
+            if shutdown::requested() {
+                return None; // interrupted -- skip the files not yet started
+            }
+            let result = process_file(
+                ocr_tracker_filepath_buf,
+                id_to_pid_map,
+                resolved_pid_map,
+                pid_url_base,
+                throttle,
+            )
+            .map_err(|e| {
+                log_debug!(
+                    tracker_path:% = ocr_tracker_filepath_buf.display(), error_kind = e.kind(), error:% = e;
+                    "rejecting ocr-tracker -- likely an organization-file"
+                );
+                ocr_tracker_filepath_buf.clone()
+            });
+            if let Some(checkpoint) = checkpoint {
+                checkpoint.save_result(ocr_tracker_filepath_buf, &result);
             }
+            Some((ocr_tracker_filepath_buf, result))
         })
         .collect();
+    let unprocessed_count: usize = ocr_tracker_filepaths.len() - results.len();
+    let resumed_count: usize = checkpoint.map_or(0, |c| c.resumed_count());
+    if let Some(checkpoint) = checkpoint {
+        checkpoint.finish();
+    }
 
     // Separate successful records and rejected paths ---------------
     let mut temp_tracker_data_vector: Vec<Record> = Vec::new();
+    let mut temp_record_paths: Vec<PathBuf> = Vec::new();
     let mut temp_rejected_paths: Vec<PathBuf> = Vec::new();
 
-    for result in results {
+    for (path, result) in results {
         match result {
-            Ok(rec) => temp_tracker_data_vector.push(rec),
+            Ok(rec) => {
+                temp_tracker_data_vector.push(rec);
+                temp_record_paths.push(path.clone());
+            }
             Err(path) => temp_rejected_paths.push(path),
         }
     }
 
     // Return results
-    Ok(PathResults {
+    PathResults {
         extracted_data_files: temp_tracker_data_vector,
+        record_paths: temp_record_paths,
         rejected_paths: temp_rejected_paths,
-    })
+        unprocessed_count,
+        resumed_count,
+    }
+}
+
+/*  -----------------------------------------------------------------
+    Parses a single ocr-tracker file into a Record, with its pid and pid-url filled in.
+    The ingest-trackers' pid wins; the pid-resolver's is the fallback.
+    Returns a read- or parse-error if the file can't be read or isn't an ocr-tracker (eg an organization-file).
+    Public for watch-mode, which re-parses trackers one at a time as they change.
+    -----------------------------------------------------------------
+*/
+pub fn process_file(
+    ocr_tracker_filepath: &Path, id_to_pid_map: &BTreeMap<String, String>,
+    resolved_pid_map: &BTreeMap<String, String>, pid_url_base: &str, throttle: &IoThrottle,
+) -> Result<Record, TrackerError> {
+    let item_num_key: String = parse_key_from_path(ocr_tracker_filepath);
+
+    // Read and parse OCR tracker file contents to Record -----------
+    let mut rec: Record = with_tracker_bytes(ocr_tracker_filepath, throttle, |bytes| {
+        schema::parse_ocr_tracker(bytes, ocr_tracker_filepath).map_err(|e| TrackerError::Parse {
+            path: ocr_tracker_filepath.to_path_buf(),
+            source: e,
+        })
+    })?;
+
+    // Look up PID and URL from hashmap
+    join_pid(
+        &mut rec,
+        &item_num_key,
+        id_to_pid_map,
+        resolved_pid_map,
+        pid_url_base,
+    );
+    Ok(rec)
+}
+
+// sets the record's pid, pid-url and pid-source from the maps; or clears them, if neither map has the key
+pub fn join_pid(
+    rec: &mut Record, item_num_key: &str, id_to_pid_map: &BTreeMap<String, String>,
+    resolved_pid_map: &BTreeMap<String, String>, pid_url_base: &str,
+) {
+    let (pid, source) = match id_to_pid_map.get(item_num_key) {
+        Some(pid) => (Some(pid.clone()), Some(PidSource::Tracker)),
+        None => match resolved_pid_map.get(item_num_key) {
+            Some(pid) => (Some(pid.clone()), Some(PidSource::Resolver)),
+            None => (None, None),
+        },
+    };
+    let url = pid.as_ref().map(|p| format!(" {}{}/", pid_url_base, p));
+    rec.pid = pid;
+    rec.pid_url = url;
+    rec.pid_source = source;
+}
+
+// sets the record's `ingest_*` columns from the item's ingest-fields; or clears them, if it has none
+pub fn join_ingest_info(
+    rec: &mut Record, item_num_key: &str, ingest_info_map: &BTreeMap<String, IngestInfo>,
+) {
+    let info: Option<&IngestInfo> = ingest_info_map.get(item_num_key);
+    rec.ingest_timestamp = info.and_then(|i| i.ingest_timestamp.clone());
+    rec.ingest_batch_id = info.and_then(|i| i.batch_id.clone());
+    rec.ingest_collection_id = info.and_then(|i| i.collection_id.clone());
+    rec.ingest_parent_pid = info.and_then(|i| i.parent_pid.clone());
+    rec.ingest_extra =
+        info.filter(|i| !i.extra.is_empty()).and_then(|i| serde_json::to_string(&i.extra).ok());
 }
 
 /*  -----------------------------------------------------------------
     Saves the data-vector to a CSV file.
+    The file-name comes from the config's `output_filename_pattern`, with the run's datestamp
+    formatted via `filename_datetime_format`.
     -----------------------------------------------------------------
 */
-pub fn save_to_csv(data: &[Record], output_dir: &str, formatted_date_time: &str) -> Result<String, String> {
-    // -- update the formatted_date_time
-    let formatted_date_time: &str = formatted_date_time.split_whitespace().next().unwrap();
-    let trimmed_datetime: &str = &formatted_date_time[0..19]; // slice up to the excluded timezone
-    let date_for_filename: String = trimmed_datetime.replace(":", "-"); // replaces colons with hyphens
+pub fn save_to_csv(
+    data: &[Record], extra_columns: &[String], output_dir: &str, run_date_time: &DateTime<Tz>,
+    filename_pattern: &str, filename_datetime_format: &str,
+) -> Result<String, TrackerError> {
+    // -- format the datestamp for the file-name
+    let date_for_filename: String = run_date_time.format(filename_datetime_format).to_string();
     log_debug!("date_for_filename: {}", &date_for_filename);
-    let file_path: String = format!("{}/tracker_output_{}.csv", output_dir, date_for_filename);
+    let file_name: String = filename_pattern.replace("{datetime}", &date_for_filename);
+    let file_path: String = format!("{}/{}", output_dir, file_name);
     // -- create the file
     let file = match File::create(&file_path) {
         Ok(file) => file,
-        Err(e) => return Err(format!("Failed to create file: {}", e)),
+        Err(e) => return Err(TrackerError::output(&file_path, e)),
     };
-    let mut wrtr = csv::Writer::from_writer(file);
     // -- write the data
-    for record in data {
-        if let Err(e) = wrtr.serialize(record) {
-            return Err(format!("Failed to serialize record: {}", e));
-        }
-    }
-    if let Err(e) = wrtr.flush() {
-        return Err(format!("Failed to flush writer: {}", e));
+    if let Err(e) = write_records_csv(data, extra_columns, file) {
+        return Err(TrackerError::output(&file_path, e));
     }
     // -- return the file-path
     Ok(file_path)
 }
 
 /*  -----------------------------------------------------------------
-    Prepares a JSON file with datestamp, elapsed, source and output paths, and error-paths.
+    The csv itself, with a header-row; also used for serve-mode's `/items.csv` and watch-mode's rewrites.
+    The header is written explicitly, since the flattened `extra` fields can't be serialized by the csv-writer;
+    each row is the record's csv_row(), with text as-is, numbers as json-numbers, and nulls as empty cells.
+    -----------------------------------------------------------------
+*/
+pub fn write_records_csv<'a, W: Write>(
+    records: impl IntoIterator<Item = &'a Record>, extra_columns: &[String], writer: W,
+) -> Result<(), csv::Error> {
+    let mut wrtr = csv::Writer::from_writer(writer);
+    let columns: Vec<String> = csv_columns(extra_columns);
+    wrtr.write_record(&columns)?;
+    let mut cells: Vec<String> = Vec::with_capacity(columns.len());
+    for record in records {
+        cells.clear();
+        for (_, value) in csv_row(record, extra_columns) {
+            cells.push(match value {
+                Value::Null => String::new(),
+                Value::String(text) => text,
+                other => other.to_string(), // numbers, bools, and any nested json
+            });
+        }
+        wrtr.write_record(&cells)?;
+    }
+    wrtr.flush()?;
+    Ok(())
+}
+
+// the Record's own fields, in order, then the promoted extra-fields
+pub fn csv_columns(extra_columns: &[String]) -> Vec<String> {
+    let mut columns: Vec<String> = fixed_columns().to_vec();
+    columns.extend(extra_columns.iter().cloned());
+    columns
+}
+
+// the Record's own fields, in order; worked out once, from a default Record (which has no extra-fields)
+fn fixed_columns() -> &'static [String] {
+    static FIXED_COLUMNS: OnceLock<Vec<String>> = OnceLock::new();
+    FIXED_COLUMNS.get_or_init(|| match serde_json::to_value(Record::default()) {
+        Ok(Value::Object(row)) => row.into_iter().map(|(k, _)| k).collect(),
+        _ => Vec::new(), // a Record always serializes to an object
+    })
+}
+
+/// Whether `name` is one of the Record's own csv-columns, so can't also be an extra-field.
+pub fn is_fixed_column(name: &str) -> bool {
+    fixed_columns().iter().any(|column| column == name)
+}
+
+/*  -----------------------------------------------------------------
+    A record as a json-object with exactly the csv's columns: its own fields, then each of `extra_columns`
+    (null if the record doesn't have it). Its other extra-fields are left out.
+    Also used for serve-mode's rows and the python bindings' records, so all three have the same columns.
+    -----------------------------------------------------------------
+*/
+pub fn csv_row(record: &Record, extra_columns: &[String]) -> Map<String, Value> {
+    let mut row: Map<String, Value> = match serde_json::to_value(record) {
+        Ok(Value::Object(row)) => row, // in field-order, since serde_json's `preserve_order` is on
+        _ => Map::new(),               // a Record always serializes to an object
+    };
+    for key in record.extra.keys().filter(|key| !is_fixed_column(key)) {
+        row.shift_remove(key); // a fixed column stays, even if an extra-field shares its name
+    }
+    for column in extra_columns {
+        let value: Value = record.extra.get(column).cloned().unwrap_or(Value::Null);
+        row.insert(column.clone(), value);
+    }
+    row
+}
+
+/*  -----------------------------------------------------------------
+    Counts, per extra-field name, the records that have it; for the summary-json's inventory of
+    fields the ocr-pipeline has added.
+    -----------------------------------------------------------------
+*/
+pub fn count_extra_fields<'a>(records: impl IntoIterator<Item = &'a Record>) -> BTreeMap<String, usize> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for record in records {
+        for key in record.extra.keys() {
+            *counts.entry(key.clone()).or_default() += 1;
+        }
+    }
+    counts
+}
+
+/*  -----------------------------------------------------------------
+    Writes `(path).tmp`, then renames it over `path`, so a reader never sees a half-written file.
+    If the write or the rename fails, the temp-file is removed, and `path` is left as it was.
+    Used by watch-mode's rewrites, the metrics-file, and the pid-resolver cache.
+    -----------------------------------------------------------------
+*/
+pub fn write_atomically(
+    path: &Path, write: impl FnOnce(&mut File) -> io::Result<()>,
+) -> Result<(), TrackerError> {
+    let mut temp_path: OsString = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+    let mut file: File = File::create(&temp_path).map_err(|e| TrackerError::output(path, e))?;
+    let result: io::Result<()> = write(&mut file).and_then(|()| {
+        drop(file); // closed before the rename
+        fs::rename(&temp_path, path)
+    });
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path); // the error that matters is the write's
+        return Err(TrackerError::output(path, e));
+    }
+    Ok(())
+}
+
+/*  -----------------------------------------------------------------
+    Marks the csv file-name of an interrupted run, so a partial csv can't be mistaken for a full one.
+    -----------------------------------------------------------------
+*/
+pub fn partial_filename_pattern(filename_pattern: &str) -> String {
+    format!("PARTIAL_{}", filename_pattern)
+}
+
+/*  -----------------------------------------------------------------
+    Prepares a JSON file with datestamp, elapsed, source and output paths, error-paths,
+    and the merged config that was used for the run.
+    The counts come from the Scanner's summary; its `status` is "complete", or "interrupted"
+    if a Ctrl-C / SIGTERM stopped process_files() early.
     -----------------------------------------------------------------
 */
 pub fn prepare_json(
-    source_dir: &str, output_dir: &str, log_level: String, csv_file_path: Option<String>,
-    ocr_data_vector_count: usize, rejected_files_count: usize, error_paths: Vec<PathBuf>,
-    start_instant: Instant, formatted_date_time: String,
+    summary: &ScanSummary, error_paths: &[PathBuf], csv_file_path: Option<String>, start_instant: Instant,
+    formatted_date_time: String, config: &Config,
 ) -> String {
+    let map: IndexMap<String, Value> = prepare_summary_map(
+        summary,
+        error_paths,
+        csv_file_path,
+        start_instant,
+        formatted_date_time,
+        config,
+    );
+    match serde_json::to_string_pretty(&map) {
+        Ok(json) => json,
+        Err(e) => format!("Error serializing output-JSON: {}", e),
+    }
+}
+
+/*  -----------------------------------------------------------------
+    Builds the map behind prepare_json(); the python bindings return it as a dict.
+    -----------------------------------------------------------------
+*/
+pub fn prepare_summary_map(
+    summary: &ScanSummary, error_paths: &[PathBuf], csv_file_path: Option<String>, start_instant: Instant,
+    formatted_date_time: String, config: &Config,
+) -> IndexMap<String, Value> {
     // -- create the main Map
     let mut map = IndexMap::<String, Value>::new();
 
-    // -- convert UTC-Time to Eastern-Time (automatically handles DST)
-    // -- update times
-    // let eastern_time = utc_now_time.with_timezone(&Eastern);
-    // let formatted_date_time = eastern_time.format("%Y-%m-%d_%H:%M:%S_%:z").to_string();
+    // -- datestamp was converted to the configured timezone, and formatted, in main()
     map.insert("datetime_stamp".to_string(), json!(formatted_date_time));
     map.insert("time_taken".to_string(), json!("temp_holder")); // the same insert-key will update it later
+    map.insert("status".to_string(), json!(summary.status.as_str()));
 
     // -- basic data
-    map.insert("source_dir_path".to_string(), json!(source_dir));
-    map.insert("output_dir_path".to_string(), json!(output_dir));
+    map.insert("source_dir_path".to_string(), json!(config.source_dir_path));
+    map.insert("output_dir_path".to_string(), json!(config.output_dir_path));
+    let log_level: &str = config.log_level.as_deref().unwrap_or("warn");
     let log_level_str = format!("`{}`; see `--help` for more info", log_level);
     map.insert("log_level".to_string(), json!(log_level_str));
 
     // -- tracker-csv path
     map.insert("tracker_output_csv_path".to_string(), json!(csv_file_path));
-    map.insert("ocr_data_vector_count".to_string(), json!(ocr_data_vector_count));
+    map.insert("ocr_data_vector_count".to_string(), json!(summary.record_count));
 
     // -- rejected-files count
     map.insert(
         "rejected_files_count_(org_tracker_files)".to_string(),
-        json!(rejected_files_count),
+        json!(summary.rejected_count),
+    );
+
+    // -- unprocessed-files count (only non-zero if interrupted)
+    map.insert(
+        "unprocessed_ocr_tracker_count".to_string(),
+        json!(summary.unprocessed_count),
+    );
+
+    // -- results reused from a checkpoint (only non-zero with `--resume`)
+    map.insert(
+        "resumed_from_checkpoint_count".to_string(),
+        json!(summary.resumed_count),
+    );
+
+    // -- pids from the pid-resolver (only with `pid_resolver_url`)
+    map.insert(
+        "resolved_pid_count".to_string(),
+        json!(summary.resolved_pid_count),
+    );
+    map.insert(
+        "pid_resolver_failed_count".to_string(),
+        json!(summary.resolver_failed_count),
+    );
+
+    // -- review outcomes (only with `reviews_path`); excluded records aren't in the csv
+    map.insert("reviewed_count".to_string(), json!(summary.reviewed_count));
+    map.insert(
+        "review_excluded_count".to_string(),
+        json!(summary.review_excluded_count),
+    );
+    map.insert("review_coverage".to_string(), json!(summary.review_coverage));
+
+    // -- records per failed validation-check (see validation.rs); with `strict`, the failing records aren't in the csv
+    map.insert(
+        "validation_failures".to_string(),
+        json!(summary.validation_failures),
+    );
+    map.insert(
+        "validation_rejected_count".to_string(),
+        json!(summary.validation_rejected_count),
+    );
+
+    // -- records per ocr-tracker schema-version (see schema.rs)
+    map.insert(
+        "ocr_schema_version_counts".to_string(),
+        json!(summary.schema_version_counts),
+    );
+
+    // -- fields the ocr-trackers have beyond the Record's own, and how many records have each
+    map.insert(
+        "ocr_extra_field_counts".to_string(),
+        json!(summary.extra_field_counts),
     );
 
     // -- error-paths
@@ -347,6 +871,15 @@ pub fn prepare_json(
     }
     map.insert("error_paths".to_string(), json!(error_paths_vec));
 
+    // -- paths the walk couldn't get into (eg permission-denied, broken symlinks)
+    map.insert("walk_errors".to_string(), json!(summary.walk_errors));
+
+    // -- concurrency actually used (the config shows what was requested)
+    map.insert("rayon_thread_count".to_string(), json!(summary.thread_count));
+
+    // -- effective config (file + cli-flags + defaults)
+    map.insert("config".to_string(), json!(config));
+
     // -- finally, update elapsed time value (the key was created above)
     let elapsed_seconds: f64 = start_instant.elapsed().as_secs_f64(); // uses monotonic clock
     log_info!("TOTAL elapsed_seconds: {}", elapsed_seconds);
@@ -357,10 +890,36 @@ pub fn prepare_json(
         format!("{:.1} minutes", elapsed_minutes)
     };
     map.insert("time_taken".to_string(), json!(elapsed_string));
+    map
+}
 
-    // -- convert the map into a JSON string
-    match serde_json::to_string_pretty(&map) {
-        Ok(json) => json,
-        Err(e) => format!("Error serializing output-JSON: {}", e),
-    }
+/*  -----------------------------------------------------------------
+    The start of the summary-map, for a run that failed before the scan finished (eg a bad
+    config or source-dir); insert_error() adds the rest.
+    -----------------------------------------------------------------
+*/
+pub fn prepare_minimal_summary_map(
+    start_instant: Instant, formatted_date_time: String, config: &Config,
+) -> IndexMap<String, Value> {
+    let mut map = IndexMap::<String, Value>::new();
+    map.insert("datetime_stamp".to_string(), json!(formatted_date_time));
+    map.insert(
+        "time_taken".to_string(),
+        json!(format!("{:.1} seconds", start_instant.elapsed().as_secs_f64())),
+    );
+    map.insert("status".to_string(), json!("failed"));
+    map.insert("source_dir_path".to_string(), json!(config.source_dir_path));
+    map.insert("output_dir_path".to_string(), json!(config.output_dir_path));
+    map.insert("config".to_string(), json!(config));
+    map
+}
+
+/*  -----------------------------------------------------------------
+    Marks a summary-map as failed: sets `status` to "failed", and puts the `error` object right after it.
+    -----------------------------------------------------------------
+*/
+pub fn insert_error(map: &mut IndexMap<String, Value>, error: &TrackerError) {
+    map.insert("status".to_string(), json!("failed"));
+    let index: usize = map.get_index_of("status").map_or(map.len(), |i| i + 1);
+    map.shift_insert(index, "error".to_string(), error.to_json());
 }
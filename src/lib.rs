@@ -0,0 +1,52 @@
+//! Parses the Hall-Hoag ocr-tracker files into records for analysis.
+//!
+//! The `parse_ocr_tracker` binary is a thin wrapper around this library; other tools can use the
+//! [`Scanner`] directly:
+//!
+//! ```no_run
+//! use parse_ocr_tracker::Scanner;
+//!
+//! let results = Scanner::builder("/path/to/hall_hoag").build()?.scan()?;
+//! for record in &results.records {
+//!     println!("{}: {:?}", record.image_name, record.pid);
+//! }
+//! # Ok::<(), parse_ocr_tracker::TrackerError>(())
+//! ```
+//!
+//! The lower-level stages ([`helper::find_json_files`], [`helper::read_ingest_trackers`],
+//! [`helper::process_files`]) are public too.
+//!
+//! [`server::ResultsServer`] serves a scan's records over http (the binary's `--serve`).
+//!
+//! [`watch::LiveResults`] keeps a scan's results current as trackers change (the binary's `--watch`).
+//!
+//! [`fixtures::generate`] builds synthetic tracker-trees, with a manifest of the expected results,
+//! for tests and benchmarks.
+//!
+//! With the `python` feature, the crate also builds a python extension-module exposing the same
+//! scanner; see `pyproject.toml`.
+
+pub mod checkpoint;
+pub mod config;
+pub mod dry_run;
+pub mod error;
+pub mod fixtures;
+pub mod helper;
+pub mod logger; // enables the log_debug!() and log_info!() macros
+pub mod metrics;
+#[cfg(feature = "python")]
+mod python;
+pub mod resolver;
+pub mod reviews;
+pub mod scanner;
+pub mod schema;
+pub mod server;
+pub mod shutdown;
+pub mod throttle;
+pub mod validation;
+pub mod watch;
+
+pub use config::Config;
+pub use error::TrackerError;
+pub use helper::{DiscoveredPaths, PidSource, Record};
+pub use scanner::{ScanResults, ScanStatus, ScanSummary, Scanner, ScannerBuilder, StageSeconds};
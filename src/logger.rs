@@ -3,12 +3,17 @@
 /// It uses the `log` crate to provide logging macros and the `chrono` crate to timestamp logs.
 ///
 /// The `log_info` and `log_debug` macros are defined to log information and debug messages respectively.
-/// These macros include the file name, module path, and line number in the log message.
+/// (`log_trace`, `log_warn`, and `log_error` work the same way.)
+/// These macros include the file name, module path, function name, and line number in the log message.
 ///
 /// The `SimpleLogger` struct is defined and implements the `Log` trait from the `log` crate.
-/// The `enabled` method checks if the log level is less than or equal to `Debug`.
+/// The `enabled` method checks the record's level against the most specific matching `LOG_LEVEL` directive.
+/// Directives look like `warn,parse_ocr_tracker::helper=debug` -- a default level, plus per-module levels.
 /// The `log` method prints the log message to the console if logging is enabled.
-/// The log message includes a timestamp, the log level, the file name, module path, line number, and the log message itself.
+/// The log message includes a timestamp, the log level, the file name, module path, function name, line number, and the log message itself.
+/// If a log-file is set (`--log_file` or `LOG_FILE`), the same lines go to that file instead of the console,
+/// rotating at a configurable size, with warnings optionally mirrored to stderr.
+/// With `LOG_FORMAT=json`, each line is instead a json object, for log-aggregators.
 ///
 /// The `LOGGER` static variable is an instance of `SimpleLogger` that can be used throughout the application.
 ///
@@ -24,13 +29,34 @@
 ///     log = logging.getLogger( __name__ )
 ///     log.debug( 'logging working' )
 ///
-/// I haven't yet been able to figure out how to log the function name.
+/// The function name isn't available from the `log` crate, so the `function_name!()` macro below
+/// gets it from the type-name of a tiny fn defined inside the calling function,
+/// and the logging macros pass it along as a `function` key-value.
 /// -----------------------------------------------------------------
+use crate::error::TrackerError;
 use chrono::Local;
-use log::{Level, LevelFilter, Metadata, Record, SetLoggerError};
-use std::env;
+use log::kv::{self, Key, Value as KvValue, VisitSource};
+use log::{Level, LevelFilter, Metadata, Record};
+use serde_json::{json, Map, Value};
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, RwLock,
+    },
+};
 
-pub static LOGGER: SimpleLogger = SimpleLogger;
+pub static LOGGER: SimpleLogger = SimpleLogger {
+    log_file: Mutex::new(None),
+    json_format: AtomicBool::new(false),
+    directives: RwLock::new(LevelDirectives {
+        default_level: LevelFilter::Warn,
+        module_levels: Vec::new(),
+    }),
+};
 
 /*
 The macros below allow me to use logging statements like:
@@ -46,67 +72,628 @@ $arg is the name of the variable that will hold the input passed to the macro.
 :tt specifies the fragment specifier. tt stands for "token tree," a flexible specifier that can capture a wide variety of syntactic constructs, including literals, identifiers, types, expressions, etc. It's used here to allow the macro to accept any Rust expression as input.
 *: This is a repetition operator, indicating that the pattern preceding it can repeat zero or more times. This allows the macro to accept a variable number of arguments.
 
+The first arm of each macro passes structured fields through to the `log` crate's key-values, like:
+`log_debug!(tracker_path:% = path.display(), item_key = key.as_str(); "some message");`
+(`:%` captures a value via `Display`, `:?` via `Debug`.)
+Text-format lines show them after the message; json-format lines put them under `fields`.
+
+Each macro also adds a `function` key-value, from `function_name!()`, which the logger pulls out
+to build the `[module-function()::line]` part of the line.
+
 
 */
 
+/*  -----------------------------------------------------------------
+    Evaluates to the name of the enclosing function, eg `process_files`.
+    How: `type_name_of_val()` of a fn defined right here gives its full path, like
+    `parse_ocr_tracker::helper::process_files::{{closure}}::f`; trim_function_name() keeps just `process_files`.
+    -----------------------------------------------------------------
+*/
+#[macro_export]
+macro_rules! function_name {
+    () => {{
+        fn f() {}
+        $crate::logger::trim_function_name(std::any::type_name_of_val(&f))
+    }};
+}
+
+pub fn trim_function_name(full_name: &'static str) -> &'static str {
+    let mut name: &'static str = full_name.strip_suffix("::f").unwrap_or(full_name);
+    while let Some(outer) = name.strip_suffix("::{{closure}}") {
+        name = outer; // log-calls inside closures report the function that holds the closure
+    }
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+const FUNCTION_KEY: &str = "function"; // the key-value added by the logging macros
+
+#[macro_export]
+macro_rules! log_trace {
+    ($($key:ident $(:$capture:tt)? = $value:expr),+; $($arg:tt)+) => {
+        log::trace!(function = $crate::function_name!(), $($key $(:$capture)? = $value),+; "{}", format_args!($($arg)+))
+    };
+    ($($arg:tt)*) => {
+        log::trace!(function = $crate::function_name!(); "{}", format_args!($($arg)*))
+    };
+}
+
 #[macro_export]
 macro_rules! log_debug {
+    ($($key:ident $(:$capture:tt)? = $value:expr),+; $($arg:tt)+) => {
+        log::debug!(function = $crate::function_name!(), $($key $(:$capture)? = $value),+; "{}", format_args!($($arg)+))
+    };
     ($($arg:tt)*) => {
-        log::debug!("{}", format_args!($($arg)*))
+        log::debug!(function = $crate::function_name!(); "{}", format_args!($($arg)*))
     };
 }
 
 #[macro_export]
 macro_rules! log_info {
+    ($($key:ident $(:$capture:tt)? = $value:expr),+; $($arg:tt)+) => {
+        log::info!(function = $crate::function_name!(), $($key $(:$capture)? = $value),+; "{}", format_args!($($arg)+))
+    };
     ($($arg:tt)*) => {
-        log::info!("{}", format_args!($($arg)*))
+        log::info!(function = $crate::function_name!(); "{}", format_args!($($arg)*))
     };
 }
 
 #[macro_export]
 macro_rules! log_warn {
+    ($($key:ident $(:$capture:tt)? = $value:expr),+; $($arg:tt)+) => {
+        log::warn!(function = $crate::function_name!(), $($key $(:$capture)? = $value),+; "{}", format_args!($($arg)+))
+    };
+    ($($arg:tt)*) => {
+        log::warn!(function = $crate::function_name!(); "{}", format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! log_error {
+    ($($key:ident $(:$capture:tt)? = $value:expr),+; $($arg:tt)+) => {
+        log::error!(function = $crate::function_name!(), $($key $(:$capture)? = $value),+; "{}", format_args!($($arg)+))
+    };
     ($($arg:tt)*) => {
-        log::warn!("{}", format_args!($($arg)*))
+        log::error!(function = $crate::function_name!(); "{}", format_args!($($arg)*))
     };
 }
 
-pub struct SimpleLogger;
+pub struct SimpleLogger {
+    log_file: Mutex<Option<RotatingLogFile>>, // `None` means log to the console
+    json_format: AtomicBool,
+    directives: RwLock<LevelDirectives>,
+}
+
+/*  -----------------------------------------------------------------
+    Parsed `LOG_LEVEL` directives, like RUST_LOG's:
+    - `debug` -- one level for everything
+    - `warn,parse_ocr_tracker::helper=debug` -- a default, plus a level for one module (and its sub-modules)
+    - `parse_ocr_tracker::helper` -- a module alone means `trace` for that module
+    The longest matching module-prefix wins.
+    -----------------------------------------------------------------
+*/
+#[derive(Debug)]
+struct LevelDirectives {
+    default_level: LevelFilter,
+    module_levels: Vec<(String, LevelFilter)>,
+}
+
+impl LevelDirectives {
+    // an unknown level-name in `module=level` falls back to `warn`, as LOG_LEVEL always has
+    fn parse(spec: &str) -> LevelDirectives {
+        let mut directives = LevelDirectives {
+            default_level: LevelFilter::Warn,
+            module_levels: Vec::new(),
+        };
+        for part in spec.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+            match part.split_once('=') {
+                Some((module, level)) => {
+                    directives.module_levels.push((module.trim().to_string(), parse_level_filter(level)))
+                }
+                None => match part.to_lowercase().parse::<LevelFilter>() {
+                    Ok(level) => directives.default_level = level,
+                    Err(_) => directives.module_levels.push((part.to_string(), LevelFilter::Trace)),
+                },
+            }
+        }
+        directives.module_levels.sort_by_key(|(module, _)| std::cmp::Reverse(module.len())); // longest prefix first
+        directives
+    }
+
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.module_levels
+            .iter()
+            .find(|(module, _)| {
+                target == module
+                    || (target.starts_with(module.as_str()) && target[module.len()..].starts_with("::"))
+            })
+            .map_or(self.default_level, |(_, level)| *level)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.module_levels.iter().map(|(_, level)| *level).fold(self.default_level, |a, b| a.max(b))
+    }
+
+    // the normalized form, for the output-json
+    fn to_spec(&self) -> String {
+        let mut parts: Vec<String> = vec![self.default_level.as_str().to_lowercase()];
+        let mut module_levels: Vec<&(String, LevelFilter)> = self.module_levels.iter().collect();
+        module_levels.sort();
+        for (module, level) in module_levels {
+            parts.push(format!("{}={}", module, level.as_str().to_lowercase()));
+        }
+        parts.join(",")
+    }
+}
+
+fn parse_level_filter(level: &str) -> LevelFilter {
+    level.trim().to_lowercase().parse::<LevelFilter>().unwrap_or(LevelFilter::Warn)
+}
 
 impl log::Log for SimpleLogger {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= Level::Debug
+        metadata.level() <= self.directives.read().unwrap().level_for(metadata.target())
     }
 
     fn log(&self, record: &Record) {
         /* if the log-level is set to `INFO`, then the `log_debug!()` macro's message will not be printed. */
         if self.enabled(record.metadata()) {
-            println!(
-                "[{}] {} [{}::{}] {}",
-                Local::now().format("%d/%b/%Y %H:%M:%S"),
-                record.level(),
-                record.file().unwrap_or("<unknown>"),
-                // record.module_path().unwrap_or("<unknown>"),  // can't determine the fuction-name
-                record.line().unwrap_or(0), // the default value of 0 implies that the line-number is not available
-                record.args()
-            );
+            let mut fields: Map<String, Value> = collect_fields(record);
+            let function: Option<String> = match fields.shift_remove(FUNCTION_KEY) {
+                Some(Value::String(function)) => Some(function),
+                _ => None, // eg, a record from a dependency's own `log` call
+            };
+            let line: String = if self.json_format.load(Ordering::Relaxed) {
+                format_json_line(record, function, fields)
+            } else {
+                format_text_line(record, function, &fields)
+            };
+            let mut log_file = self.log_file.lock().unwrap();
+            match log_file.as_mut() {
+                Some(log_file) => {
+                    if log_file.mirror_warnings_to_stderr && record.level() <= Level::Warn {
+                        eprintln!("{}", line);
+                    }
+                    if let Err(e) = log_file.write_line(&line) {
+                        eprintln!("{} (log-file write failed: {})", line, e); // don't lose the message
+                    }
+                }
+                None => println!("{}", line),
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(log_file) = self.log_file.lock().unwrap().as_mut() {
+            let _ = log_file.file.flush();
+        }
+    }
+}
+
+// matches the python format: `[%(asctime)s] %(levelname)s [%(module)s-%(funcName)s()::%(lineno)d] %(message)s`,
+// with the file-name ahead of the module, eg `[src/helper.rs parse_ocr_tracker::helper-process_files()::482]`
+fn format_text_line(record: &Record, function: Option<String>, fields: &Map<String, Value>) -> String {
+    let location: String = match function {
+        Some(function) => format!("{}-{}()", record.module_path().unwrap_or("<unknown>"), function),
+        None => record.module_path().unwrap_or("<unknown>").to_string(),
+    };
+    let mut line: String = format!(
+        "[{}] {} [{} {}::{}] {}",
+        Local::now().format("%d/%b/%Y %H:%M:%S"),
+        record.level(),
+        record.file().unwrap_or("<unknown>"),
+        location,
+        record.line().unwrap_or(0), // the default value of 0 implies that the line-number is not available
+        record.args()
+    );
+    for (key, value) in fields {
+        match value {
+            Value::String(s) => line.push_str(&format!(" {}=``{}``", key, s)),
+            other => line.push_str(&format!(" {}={}", key, other)),
+        }
+    }
+    line
+}
+
+fn format_json_line(record: &Record, function: Option<String>, fields: Map<String, Value>) -> String {
+    let entry: Value = json!({
+        "timestamp": Local::now().to_rfc3339(),
+        "level": record.level().as_str(),
+        "file": record.file(),
+        "line": record.line(),
+        "module_path": record.module_path(),
+        "function": function,
+        "message": record.args().to_string(),
+        "fields": fields,
+    });
+    entry.to_string()
+}
+
+/*  -----------------------------------------------------------------
+    Gathers a record's key-values (eg `tracker_path`, `item_key`) into a json-map,
+    keeping numbers and booleans typed.
+    -----------------------------------------------------------------
+*/
+fn collect_fields(record: &Record) -> Map<String, Value> {
+    struct FieldCollector(Map<String, Value>);
+    impl<'kvs> VisitSource<'kvs> for FieldCollector {
+        fn visit_pair(&mut self, key: Key<'kvs>, value: KvValue<'kvs>) -> Result<(), kv::Error> {
+            let value: Value = if let Some(b) = value.to_bool() {
+                json!(b)
+            } else if let Some(i) = value.to_i64() {
+                json!(i)
+            } else if let Some(u) = value.to_u64() {
+                json!(u)
+            } else if let Some(f) = value.to_f64() {
+                json!(f)
+            } else {
+                json!(value.to_string())
+            };
+            self.0.insert(key.to_string(), value);
+            Ok(())
         }
     }
+    let mut collector = FieldCollector(Map::new());
+    let _ = record.key_values().visit(&mut collector); // the collector never returns an error
+    collector.0
+}
 
-    fn flush(&self) {} // my output to the console doesn't require this, but it's required by the `Log` trait
+/*  -----------------------------------------------------------------
+    A log-file that rotates by size, like python's RotatingFileHandler:
+    when the next line would push it past `max_bytes`, `foo.log` becomes `foo.log.1`,
+    `foo.log.1` becomes `foo.log.2`, and so on, keeping `backup_count` old files.
+    Writes are buffered; the buffer is flushed before each rotation, and by `log::logger().flush()` at shutdown.
+    -----------------------------------------------------------------
+*/
+struct RotatingLogFile {
+    path: PathBuf,
+    file: BufWriter<File>,
+    current_bytes: u64,
+    max_bytes: u64,
+    backup_count: usize,
+    mirror_warnings_to_stderr: bool,
 }
 
-pub fn init_logger() -> Result<(), SetLoggerError> {
+impl RotatingLogFile {
+    fn open(
+        path: PathBuf, max_bytes: u64, backup_count: usize, mirror_warnings_to_stderr: bool,
+    ) -> std::io::Result<Self> {
+        let file: File = OpenOptions::new().create(true).append(true).open(&path)?;
+        let current_bytes: u64 = file.metadata()?.len();
+        Ok(RotatingLogFile {
+            path,
+            file: BufWriter::new(file),
+            current_bytes,
+            max_bytes,
+            backup_count,
+            mirror_warnings_to_stderr,
+        })
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        let line_bytes: u64 = line.len() as u64 + 1; // +1 for the newline
+        if self.max_bytes > 0 && self.current_bytes > 0 && self.current_bytes + line_bytes > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.current_bytes += line_bytes;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        if self.backup_count > 0 {
+            // -- shift `foo.log.(n-1)` -> `foo.log.n`, dropping the oldest
+            for i in (1..self.backup_count).rev() {
+                let from: PathBuf = self.backup_path(i);
+                if from.exists() {
+                    fs::rename(&from, self.backup_path(i + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.backup_path(1))?;
+        }
+        self.file = BufWriter::new(File::create(&self.path)?); // truncates, when there are no backups to keep
+        self.current_bytes = 0;
+        Ok(())
+    }
+
+    fn backup_path(&self, i: usize) -> PathBuf {
+        let mut backup = self.path.clone().into_os_string();
+        backup.push(format!(".{}", i));
+        PathBuf::from(backup)
+    }
+}
+
+pub fn init_logger() -> Result<(), TrackerError> {
     // let log_level = env::var("LOG_LEVEL").unwrap_or_else(|_| "debug".to_string());
     let log_level = env::var("LOG_LEVEL").unwrap_or_else(|_| "warn".to_string());
-    let level_filter = match log_level.to_lowercase().as_str() {
-        "debug" => LevelFilter::Debug,
-        "info" => LevelFilter::Info,
-        _ => LevelFilter::Warn,
-    };
 
     /*
        In the line below, the part between the vertical pipes is the cloaure `arcument`.
-       The part `log::set_max_level(level_filter)` is the closure `body`.
+       The part `set_log_level(&log_level)` is the closure `body`.
     */
-    log::set_logger(&LOGGER).map(|()| log::set_max_level(level_filter))
+    log::set_logger(&LOGGER)
+        .map(|()| {
+            set_log_level(&log_level);
+        })
+        .map_err(|e| TrackerError::Config(format!("Failed to init the logger: {}", e)))
+}
+
+/*  -----------------------------------------------------------------
+    Sets the level-directives (eg `warn,parse_ocr_tracker::helper=debug`); called at init,
+    and again once the config-file and cli-flags are known.
+    Returns the normalized directives, for the output-json.
+    -----------------------------------------------------------------
+*/
+pub fn set_log_level(log_level: &str) -> String {
+    let directives = LevelDirectives::parse(log_level);
+    log::set_max_level(directives.max_level()); // lets the `log` macros skip anything no directive wants
+    let spec: String = directives.to_spec();
+    *LOGGER.directives.write().unwrap() = directives;
+    spec
+}
+
+/*  -----------------------------------------------------------------
+    Sets the line-format: "text" (the default) or "json" (one object per line).
+    Returns the normalized format-name, for the output-json.
+    -----------------------------------------------------------------
+*/
+pub fn set_log_format(log_format: &str) -> Result<String, TrackerError> {
+    match log_format.to_lowercase().as_str() {
+        "text" => {
+            LOGGER.json_format.store(false, Ordering::Relaxed);
+            Ok("text".to_string())
+        }
+        "json" => {
+            LOGGER.json_format.store(true, Ordering::Relaxed);
+            Ok("json".to_string())
+        }
+        _ => Err(TrackerError::Config(format!(
+            "log-format ``{}`` must be `text` or `json`",
+            log_format
+        ))),
+    }
+}
+
+/*  -----------------------------------------------------------------
+    Switches logging from the console to a rotating log-file.
+    `max_bytes` of 0 disables rotation.
+    -----------------------------------------------------------------
+*/
+pub fn set_log_file(
+    path: &str, max_bytes: u64, backup_count: usize, mirror_warnings_to_stderr: bool,
+) -> Result<(), TrackerError> {
+    let log_file = RotatingLogFile::open(
+        PathBuf::from(path),
+        max_bytes,
+        backup_count,
+        mirror_warnings_to_stderr,
+    )
+    .map_err(|e| TrackerError::output(path, e))?;
+    *LOGGER.log_file.lock().unwrap() = Some(log_file);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_longest_matching_module_prefix_wins() {
+        let directives =
+            LevelDirectives::parse("info,parse_ocr_tracker=warn,parse_ocr_tracker::helper=trace");
+        assert_eq!(
+            directives.level_for("parse_ocr_tracker::helper"),
+            LevelFilter::Trace
+        );
+        assert_eq!(
+            directives.level_for("parse_ocr_tracker::helper::inner"),
+            LevelFilter::Trace
+        );
+        assert_eq!(
+            directives.level_for("parse_ocr_tracker::config"),
+            LevelFilter::Warn
+        );
+        assert_eq!(directives.level_for("parse_ocr_tracker_extra"), LevelFilter::Info); // not a sub-module
+        assert_eq!(directives.level_for("rayon_core"), LevelFilter::Info);
+        assert_eq!(directives.max_level(), LevelFilter::Trace);
+    }
+
+    #[test]
+    fn invalid_specs_fall_back() {
+        let directives = LevelDirectives::parse("parse_ocr_tracker::helper=loud");
+        assert_eq!(
+            directives.level_for("parse_ocr_tracker::helper"),
+            LevelFilter::Warn
+        );
+        let directives = LevelDirectives::parse("loud"); // not a level, so taken as a module-name
+        assert_eq!(directives.level_for("loud"), LevelFilter::Trace);
+        assert_eq!(directives.level_for("parse_ocr_tracker"), LevelFilter::Warn);
+        assert_eq!(LevelDirectives::parse(" , ,").to_spec(), "warn");
+    }
+
+    #[test]
+    fn to_spec_normalizes_and_round_trips() {
+        for spec in [
+            "DEBUG",
+            " parse_ocr_tracker::helper = Debug , info",
+            "parse_ocr_tracker::schema,error,parse_ocr_tracker=info",
+        ] {
+            let normalized: String = LevelDirectives::parse(spec).to_spec();
+            assert_eq!(LevelDirectives::parse(&normalized).to_spec(), normalized);
+        }
+        assert_eq!(
+            LevelDirectives::parse(" parse_ocr_tracker::helper = Debug , info").to_spec(),
+            "info,parse_ocr_tracker::helper=debug"
+        );
+        assert_eq!(
+            LevelDirectives::parse("parse_ocr_tracker::schema,error").to_spec(),
+            "error,parse_ocr_tracker::schema=trace"
+        );
+    }
+
+    // a record as the logging macros build it, with one field of each kind
+    fn with_test_record(json_format: bool) -> String {
+        let fields: [(&str, KvValue); 5] = [
+            (FUNCTION_KEY, KvValue::from("process_files")),
+            (
+                "tracker_path",
+                KvValue::from("HH001545/HH001545_0001/HH001545_0001-ocr_complete.json"),
+            ),
+            ("word_count", KvValue::from(120i64)),
+            ("avg_confidence", KvValue::from(88.5f64)),
+            ("is_placeholder", KvValue::from(false)),
+        ];
+        let args = format_args!("parsed {}", "HH001545_0001");
+        let record: Record = Record::builder()
+            .args(args)
+            .level(Level::Info)
+            .target("parse_ocr_tracker::helper")
+            .module_path_static(Some("parse_ocr_tracker::helper"))
+            .file_static(Some("src/helper.rs"))
+            .line(Some(482))
+            .key_values(&fields)
+            .build();
+        let mut collected: Map<String, Value> = collect_fields(&record);
+        let function: Option<String> =
+            collected.shift_remove(FUNCTION_KEY).map(|f| f.as_str().unwrap().to_string());
+        if json_format {
+            format_json_line(&record, function, collected)
+        } else {
+            format_text_line(&record, function, &collected)
+        }
+    }
+
+    #[test]
+    fn json_lines_have_the_location_and_typed_fields() {
+        let entry: Value = serde_json::from_str(&with_test_record(true)).unwrap();
+        let keys: Vec<&str> = entry.as_object().unwrap().keys().map(|key| key.as_str()).collect();
+        assert_eq!(
+            keys,
+            [
+                "timestamp",
+                "level",
+                "file",
+                "line",
+                "module_path",
+                "function",
+                "message",
+                "fields"
+            ]
+        );
+        assert_eq!(entry["level"], "INFO");
+        assert_eq!(entry["file"], "src/helper.rs");
+        assert_eq!(entry["line"], 482);
+        assert_eq!(entry["module_path"], "parse_ocr_tracker::helper");
+        assert_eq!(entry["function"], "process_files");
+        assert_eq!(entry["message"], "parsed HH001545_0001");
+        assert_eq!(
+            entry["fields"],
+            json!({
+                "tracker_path": "HH001545/HH001545_0001/HH001545_0001-ocr_complete.json",
+                "word_count": 120,
+                "avg_confidence": 88.5,
+                "is_placeholder": false,
+            })
+        );
+    }
+
+    #[test]
+    fn text_lines_have_the_file_module_function_and_line() {
+        let line: String = with_test_record(false);
+        assert!(line.ends_with(
+            "] INFO [src/helper.rs parse_ocr_tracker::helper-process_files()::482] parsed HH001545_0001 \
+             tracker_path=``HH001545/HH001545_0001/HH001545_0001-ocr_complete.json`` word_count=120 \
+             avg_confidence=88.5 is_placeholder=false"
+        ));
+    }
+
+    struct Wrapper<T>(T);
+
+    impl<T: Clone> Wrapper<T> {
+        fn name_from_method(&self) -> &'static str {
+            crate::function_name!()
+        }
+    }
+
+    fn name_from_generic_fn<T: Default>() -> &'static str {
+        let _ = T::default();
+        crate::function_name!()
+    }
+
+    #[test]
+    fn function_names_are_trimmed_to_the_enclosing_fn() {
+        assert_eq!(
+            crate::function_name!(),
+            "function_names_are_trimmed_to_the_enclosing_fn"
+        );
+        let names: Vec<&str> = [1].iter().flat_map(|_| [2].iter().map(|_| crate::function_name!())).collect();
+        assert_eq!(names, ["function_names_are_trimmed_to_the_enclosing_fn"]); // from nested closures
+        assert_eq!(name_from_generic_fn::<Vec<String>>(), "name_from_generic_fn");
+        assert_eq!(Wrapper(String::new()).name_from_method(), "name_from_method");
+        // -- the type-name shapes themselves
+        assert_eq!(
+            trim_function_name("parse_ocr_tracker::helper::process_files::f"),
+            "process_files"
+        );
+        assert_eq!(
+            trim_function_name("parse_ocr_tracker::helper::process_files::{{closure}}::{{closure}}::f"),
+            "process_files"
+        );
+        assert_eq!(
+            trim_function_name(
+                "<parse_ocr_tracker::Wrapper<alloc::string::String> as core::fmt::Debug>::fmt::f"
+            ),
+            "fmt"
+        );
+        assert_eq!(trim_function_name("not_a_path"), "not_a_path");
+    }
+
+    #[test]
+    fn log_files_rotate_at_the_size_limit_keeping_backup_count_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path: PathBuf = dir.path().join("tracker.log");
+        let mut log_file = RotatingLogFile::open(path.clone(), 100, 2, false).unwrap();
+        let line: String = "x".repeat(29); // 30 bytes with its newline, so 3 lines per file
+        for _ in 0..10 {
+            log_file.write_line(&line).unwrap();
+        }
+        // -- the rotated files were flushed whole; the current one is still buffered
+        let backup_1: PathBuf = log_file.backup_path(1);
+        let backup_2: PathBuf = log_file.backup_path(2);
+        assert_eq!(
+            fs::read_to_string(&backup_1).unwrap(),
+            format!("{}\n", line).repeat(3)
+        );
+        assert_eq!(fs::read_to_string(&backup_2).unwrap().len(), 90);
+        assert!(!log_file.backup_path(3).exists());
+        assert_eq!(fs::metadata(&path).unwrap().len(), 0);
+        log_file.file.flush().unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().len(), 30);
+
+        // -- reopening picks up the current size
+        drop(log_file);
+        let mut log_file = RotatingLogFile::open(path.clone(), 100, 2, false).unwrap();
+        log_file.write_line(&line).unwrap();
+        log_file.write_line(&line).unwrap();
+        log_file.write_line(&line).unwrap();
+        log_file.file.flush().unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().len(), 30);
+        assert_eq!(fs::metadata(&backup_1).unwrap().len(), 90);
+    }
+
+    #[test]
+    fn without_backups_the_log_file_is_truncated() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path: PathBuf = dir.path().join("tracker.log");
+        let mut log_file = RotatingLogFile::open(path.clone(), 100, 0, false).unwrap();
+        for n in 0..5 {
+            log_file.write_line(&format!("{:029}", n)).unwrap();
+        }
+        log_file.file.flush().unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!("{:029}\n{:029}\n", 3, 4)
+        );
+        assert!(!log_file.backup_path(1).exists());
+    }
 }
@@ -1,13 +1,19 @@
-mod helper;
-pub mod logger; // enables the log_debug!() and log_info!() macros
-
-use crate::helper::Record;
 use chrono::{DateTime, Utc};
-use chrono_tz::{Tz, US::Eastern};
+use chrono_tz::Tz;
 use clap::{arg, Command};
+use indexmap::IndexMap;
+use parse_ocr_tracker::checkpoint::Checkpoint;
+use parse_ocr_tracker::metrics::{self, OutputSeconds};
+use parse_ocr_tracker::server::{ResultsServer, ServedScan};
+use parse_ocr_tracker::watch::{self, BatchCounts, LiveResults};
+use parse_ocr_tracker::{config, dry_run, helper, logger, shutdown};
+use parse_ocr_tracker::{log_info, log_warn};
+use parse_ocr_tracker::{Config, DiscoveredPaths, ScanResults, ScanStatus, Scanner, TrackerError};
+use serde_json::Value;
 use std::env;
-use std::path::PathBuf;
-use std::time::Instant;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 /*  -----------------------------------------------------------------
     Includes the file generated by the build.rs script, which looks like:
@@ -17,124 +23,460 @@ use std::time::Instant;
 */
 include!(concat!(env!("OUT_DIR"), "/git_commit.rs")); // OUT_DIR is set by cargo; is the target dir; and is only available during build process
 
+/*  -----------------------------------------------------------------
+    What run() has built so far; on failure, main() reports from it as much as is known.
+    -----------------------------------------------------------------
+*/
+#[derive(Default)]
+struct RunState {
+    config: Option<Config>,
+    results: Option<ScanResults>,
+}
+
 /*  -----------------------------------------------------------------
     Main manager function.
+    Exit-codes: 0 on success; 130 if interrupted; otherwise the failure-category's code (see error.rs).
     -----------------------------------------------------------------
 */
 fn main() {
     // -- create start-times ----------------------------------------
     let start_instant = Instant::now(); // monotonic clock starts -- passed to prepare_json()
     let datestamp_time = Utc::now(); // for time-zone aware datestamp for output json
-    let eastern_time: DateTime<Tz> = datestamp_time.with_timezone(&Eastern); // converts UTC-Time to Eastern-Time (automatically handles DST)
-    let formatted_date_time: String = eastern_time.format("%Y-%m-%d_%H:%M:%S_%:z").to_string();
 
+    let mut state = RunState::default();
+    let exit_code: i32 = match run(&mut state, start_instant, datestamp_time) {
+        Ok(ScanStatus::Complete) => 0,
+        Ok(ScanStatus::Interrupted) => shutdown::INTERRUPTED_EXIT_CODE,
+        Err(e) => {
+            eprintln!("{}", e);
+            println!(
+                "{}",
+                prepare_failure_json(&e, state, start_instant, datestamp_time)
+            );
+            e.exit_code()
+        }
+    };
+    log::logger().flush();
+    if exit_code != 0 {
+        std::process::exit(exit_code);
+    }
+}
+
+/*  -----------------------------------------------------------------
+    Does the run; returns whether it completed or was interrupted.
+    -----------------------------------------------------------------
+*/
+fn run(
+    state: &mut RunState, start_instant: Instant, datestamp_time: DateTime<Utc>,
+) -> Result<ScanStatus, TrackerError> {
     // init logger --------------------------------------------------
-    logger::init_logger().unwrap();
+    logger::init_logger()?;
+
+    // setup and read cli-args --------------------------------------
+    let matches = cli().get_matches();
+
+    // build config -------------------------------------------------
+    // -- defaults, then the config-file, then the cli-flags
+    let mut config: Config = match matches.get_one::<String>("config") {
+        Some(config_path) => Config::from_toml_file(config_path)?,
+        None => Config::default(),
+    };
+    config.apply_cli_args(&matches);
+    state.config = Some(config.clone()); // so a failure-json can echo it
+    config.validate()?;
 
     // grab log-level -----------------------------------------------
-    // -- only needed for json output -- grabbed by logger::init_logger()
-    let mut log_level: String = env::var("LOG_LEVEL").unwrap_or_else(|_| "warn".to_string());
-    log_level = log_level.to_lowercase();
-    if log_level != "debug" && log_level != "info" && log_level != "warn" {
-        log_level = "warn".to_string();
+    // -- cli-flag or config-file wins; otherwise the `LOG_LEVEL` envar already read by logger::init_logger()
+    let requested_log_level: String = config
+        .log_level
+        .clone()
+        .unwrap_or_else(|| env::var("LOG_LEVEL").unwrap_or_else(|_| "warn".to_string()));
+    let log_level: String = logger::set_log_level(&requested_log_level);
+    config.log_level = Some(log_level); // so the echoed config shows the effective level
+
+    // set log-format -----------------------------------------------
+    // -- cli-flag or config-file wins; otherwise the `LOG_FORMAT` envar; otherwise "text"
+    let requested_log_format: String = config
+        .log_format
+        .clone()
+        .unwrap_or_else(|| env::var("LOG_FORMAT").unwrap_or_else(|_| "text".to_string()));
+    config.log_format = Some(logger::set_log_format(&requested_log_format)?);
+
+    // set up log-file ----------------------------------------------
+    // -- cli-flag or config-file wins; otherwise the `LOG_FILE` envar; otherwise the console
+    if config.log_file.is_none() {
+        config.log_file = env::var("LOG_FILE").ok().filter(|path| !path.is_empty());
     }
+    if let Some(log_file) = &config.log_file {
+        logger::set_log_file(
+            log_file,
+            config.log_max_bytes,
+            config.log_backup_count,
+            config.log_warnings_to_stderr,
+        )?;
+    }
+    state.config = Some(config.clone());
 
-    // setup and read cli-args --------------------------------------
-    let about_text = r#"Info...
-  - Walks `source_dir_path` and creates `(output_dir_path)/tracker_output.csv`.
-  - Logs to console only; default log-level is 'warn'; use `export LOG_LEVEL="debug"` or "info" to see more output.
-  - Useful json is returned with paths, counts, and error-filepaths."#;
-    let matches = Command::new("parse_ocr_tracker")
-        .version(GIT_COMMIT)
-        .about(about_text)
-        .arg(arg!(-s --source_dir_path <VALUE>).required(true))
-        .arg(arg!(-o --output_dir_path <VALUE>).required(true))
-        .get_matches();
-
-    // get source_dir -----------------------------------------------
-    let source_dir_temp_ref: &String = matches
-        .get_one::<String>("source_dir_path")
-        .expect("Failed to get required 'source_dir_path' argument.");
-    let source_dir: &str = source_dir_temp_ref.as_str(); // or... let source_dir: String = source_dir_temp_ref.to_string();
-                                                         // log_debug!("source-arg: {:?}", source_dir);
-    log_info!("source-arg: {:?}", source_dir);
+    // handle Ctrl-C / SIGTERM --------------------------------------
+    // -- lets an interrupted run still save a partial csv and print the json
+    if let Err(e) = shutdown::install_handler() {
+        log_warn!("Failed to install the Ctrl-C / SIGTERM handler: {}", e);
+    }
+
+    // serve-mode ---------------------------------------------------
+    // -- listen first, so a bad address fails before a long scan
+    let server: Option<ResultsServer> = config.serve.as_deref().map(ResultsServer::bind).transpose()?;
+    if let (Some(server), Some(csv_path)) = (&server, &config.serve_csv_path) {
+        // -- an earlier run's csv; no scan
+        let served_scan = ServedScan::from_csv(Path::new(csv_path))?;
+        print_serving(server, csv_path);
+        server.run(&served_scan);
+        return Ok(ScanStatus::Complete);
+    }
+
+    // build scanner ------------------------------------------------
+    // -- the library does the work; it sizes its own thread-pool from `threads`
+    let scanner: Scanner = Scanner::from_config(&config)?;
+
+    // create datestamp ---------------------------------------------
+    let timezone: Tz = config.timezone()?;
+    let zoned_time: DateTime<Tz> = datestamp_time.with_timezone(&timezone); // converts UTC-Time to the configured zone (automatically handles DST)
+    let formatted_date_time: String = zoned_time.format(&config.datetime_format).to_string();
+
+    // -- dry-run: inventory the paths and stop ---------------------
+    if config.dry_run {
+        let source_dir: &str = scanner.source_dir_path();
+        log_info!("source-arg: {:?}", source_dir);
+        let find_json_files_start_instant = Instant::now();
+        let paths: DiscoveredPaths = scanner.discover()?;
+        let elapsed_seconds: f64 = find_json_files_start_instant.elapsed().as_secs_f64(); // uses monotonic clock
+        println!("json-paths gathered (took, {} seconds)", elapsed_seconds);
+        let inventory_json: String = scanner.install(|| {
+            dry_run::prepare_inventory_json(source_dir, &paths, start_instant, formatted_date_time, &config)
+        });
+        println!("{}", inventory_json);
+        return Ok(ScanStatus::Complete);
+    }
 
     // get output_dir -----------------------------------------------
-    let output_dir_temp_ref: &String = matches
-        .get_one::<String>("output_dir_path")
-        .expect("Failed to get required 'output_dir_path' argument.");
-    let output_dir: &str = output_dir_temp_ref.as_str();
+    // -- (only needed past this point; a dry-run doesn't require it)
+    let output_dir: &str = config
+        .output_dir_path
+        .as_deref()
+        .ok_or_else(|| TrackerError::Config("`output_dir_path` must be set".to_string()))?;
     log_info!("output-arg: {:?}", output_dir);
 
-    // get paths ----------------------------------------------------
-    let find_json_files_start_instant = Instant::now();
-    let (ocr_paths, ingest_paths, error_paths, other_paths) = helper::find_json_files(source_dir);
-    let elapsed_seconds: f64 = find_json_files_start_instant.elapsed().as_secs_f64(); // uses monotonic clock
-    println!("json-paths gathered (took, {} seconds)", elapsed_seconds);
-
-    let ocr_tracker_paths_count = ocr_paths.len();
-    log_debug!("len(ocr_paths): {}", ocr_tracker_paths_count);
-    let _ingest_tracker_paths_count = ingest_paths.len();
-    let _error_tracker_paths_count = error_paths.len();
-    let _other_json_paths_count = other_paths.len();
-
-    // make a map of id-to-pid --------------------------------------
-    let make_id_to_pid_map_instant = Instant::now();
-    let id_to_pid_map = helper::make_id_to_pid_map(ingest_paths);
-    let elapsed_seconds: f64 = make_id_to_pid_map_instant.elapsed().as_secs_f64(); // uses monotonic clock
-    println!("id-to-pid-map created (took, {} seconds", elapsed_seconds);
-
-    // -- process ocr-tracker-files ---------------------------------
-    let process_files_instant = Instant::now();
-    let path_results: helper::PathResults = helper::process_files(ocr_paths, &id_to_pid_map) // PathResults is a struct just to hold and return the two vectors
-        .unwrap_or_else(|e| {
-            eprintln!("Failed to process the ocr-tracker-files: {}", e);
-            std::process::exit(1); // Exit or handle the error by returning a default value or performing other actions
-        });
-    let elapsed_seconds: f64 = process_files_instant.elapsed().as_secs_f64(); // uses monotonic clock
-    println!("ocr-stats gathered (took {} seconds)", elapsed_seconds);
-
-    let data_vector: Vec<Record> = path_results.extracted_data_files;
-    let ocr_data_vector_count: usize = data_vector.len();
-    let rejected_files: Vec<PathBuf> = path_results.rejected_paths;
-    log_debug!("all rejected_file paths...");
-    for file in &rejected_files {
-        log_debug!("{:?}", file);
+    // -- open checkpoint -------------------------------------------
+    // -- with `--resume`, loads the results of the earlier run so those files aren't re-parsed
+    let checkpoint: Option<Checkpoint> = if config.checkpoint_every > 0 {
+        match Checkpoint::open(output_dir, config.resume, config.checkpoint_every) {
+            Ok(checkpoint) => Some(checkpoint),
+            Err(e) if config.resume => return Err(e),
+            Err(e) => {
+                log_warn!("{}; continuing without checkpoints", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // -- scan: find paths, make the id-to-pid map, process ocr-tracker-files
+    let results: &ScanResults = state.results.insert(scanner.scan_with_checkpoint(checkpoint.as_ref())?);
+    let stage_seconds = &results.summary.stage_seconds;
+    println!(
+        "json-paths gathered (took, {} seconds)",
+        stage_seconds.find_json_files
+    );
+    println!(
+        "id-to-pid-map created (took, {} seconds",
+        stage_seconds.make_id_to_pid_map
+    );
+    if config.pid_resolver_url.is_some() {
+        println!("pids resolved (took {} seconds)", stage_seconds.resolve_pids);
     }
-    let rejected_files_count: usize = rejected_files.len();
+    println!(
+        "ocr-stats gathered (took {} seconds)",
+        stage_seconds.process_files
+    );
+    let status: ScanStatus = results.summary.status;
 
     // -- save csv --------------------------------------------------
+    // -- an interrupted run saves what was parsed so far, to a clearly-marked partial file
     let save_csv_instant = Instant::now();
-    let csv_file_path = helper::save_to_csv(&data_vector, output_dir, &formatted_date_time);
-    let csv_file_path: Option<String> = match csv_file_path {
-        Ok(file_path) => {
-            log_info!("CSV saved successfully at: {}", file_path);
-            Some(file_path)
-        }
-        Err(e) => {
-            log_info!("Error saving to CSV: {}", e);
-            None // or handle the error as needed
-        }
+    let filename_pattern: String = if status == ScanStatus::Interrupted {
+        helper::partial_filename_pattern(&config.output_filename_pattern)
+    } else {
+        config.output_filename_pattern.clone()
     };
-    let elapsed_seconds: f64 = save_csv_instant.elapsed().as_secs_f64(); // uses monotonic clock
-    println!("csv saved (took {} seconds)", elapsed_seconds);
+    let csv_file_path: String = helper::save_to_csv(
+        &results.records,
+        &config.extra_columns,
+        output_dir,
+        &zoned_time,
+        &filename_pattern,
+        &config.filename_datetime_format,
+    )?;
+    log_info!("CSV saved successfully at: {}", csv_file_path);
+    let mut output_seconds = OutputSeconds {
+        save_csv: save_csv_instant.elapsed().as_secs_f64(), // uses monotonic clock
+        ..OutputSeconds::default()
+    };
+    println!("csv saved (took {} seconds)", output_seconds.save_csv);
+
+    // -- a complete, saved run no longer needs its checkpoint ------
+    if let Some(checkpoint) = checkpoint {
+        if status == ScanStatus::Complete {
+            checkpoint.remove();
+        }
+    }
 
     // prepare json -------------------------------------------------
     let prepare_json_instant = Instant::now();
     let return_json: String = helper::prepare_json(
-        source_dir,
-        output_dir,
-        log_level,
-        csv_file_path,
-        ocr_data_vector_count,
-        rejected_files_count,
-        error_paths,
+        &results.summary,
+        &results.error_tracker_paths,
+        Some(csv_file_path.clone()),
         start_instant,
         formatted_date_time,
+        &config,
     );
-    let elapsed_seconds: f64 = prepare_json_instant.elapsed().as_secs_f64(); // uses monotonic clock
-    println!("final json prepared (took {} seconds)", elapsed_seconds);
+    output_seconds.prepare_json = prepare_json_instant.elapsed().as_secs_f64(); // uses monotonic clock
+    println!(
+        "final json prepared (took {} seconds)",
+        output_seconds.prepare_json
+    );
+
+    // -- metrics-file, for the node-exporter textfile-collector ----
+    if let Some(metrics_file) = &config.metrics_file {
+        let metrics_text: String =
+            metrics::render(&results.summary, &results.records, &output_seconds, Utc::now());
+        metrics::write_metrics_file(Path::new(metrics_file), &metrics_text)?;
+        log_info!("metrics written to: {}", metrics_file);
+    }
     println!("{}", return_json);
+
+    // -- serve-mode: answer requests about the results until Ctrl-C
+    if let Some(server) = server {
+        if status == ScanStatus::Complete {
+            let summary: Value = serde_json::from_str(&return_json).unwrap_or(Value::Null);
+            let served_scan = ServedScan::new(
+                results.records.clone(),
+                &results.record_paths,
+                config.extra_columns.clone(),
+                summary,
+            );
+            print_serving(&server, &csv_file_path);
+            server.run(&served_scan);
+        }
+    }
+
+    // -- watch-mode: keep the csv and summary current until Ctrl-C
+    if config.watch && status == ScanStatus::Complete {
+        let live = LiveResults::from_scan(results.clone(), &config);
+        watch_outputs(
+            &scanner,
+            live,
+            &csv_file_path,
+            start_instant,
+            &output_seconds,
+            &config,
+        )?;
+    }
+    Ok(status)
+}
+
+/*  -----------------------------------------------------------------
+    Watch-mode: after each batch of tracker-changes, rewrites the run's csv in place, and writes the
+    updated summary-json beside it, as `(csv-file-stem).summary.json`; and the metrics-file, if set.
+    All are written to a temp-file and renamed, so a reader never sees a half-written file.
+    -----------------------------------------------------------------
+*/
+fn watch_outputs(
+    scanner: &Scanner, mut live: LiveResults, csv_file_path: &str, start_instant: Instant,
+    output_seconds: &OutputSeconds, config: &Config,
+) -> Result<(), TrackerError> {
+    let csv_path: &Path = Path::new(csv_file_path);
+    let summary_path: PathBuf = csv_path.with_extension("summary.json");
+    let timezone: Tz = config.timezone()?;
+    let debounce = Duration::from_secs(config.watch_debounce_seconds);
+    println!(
+        "watching ``{}`` for tracker-changes (Ctrl-C to stop)",
+        scanner.source_dir_path()
+    );
+    watch::watch_source_dir(
+        Path::new(scanner.source_dir_path()),
+        debounce,
+        shutdown::requested,
+        |changed_paths| {
+            let counts: BatchCounts = live.apply(&changed_paths, scanner);
+            helper::write_atomically(csv_path, |file| {
+                helper::write_records_csv(live.records(), &config.extra_columns, file)
+                    .map_err(io::Error::other)
+            })?;
+            let formatted_date_time: String =
+                Utc::now().with_timezone(&timezone).format(&config.datetime_format).to_string();
+            let summary_json: String = helper::prepare_json(
+                live.summary(),
+                &live.error_tracker_paths(),
+                Some(csv_file_path.to_string()),
+                start_instant,
+                formatted_date_time,
+                config,
+            );
+            helper::write_atomically(&summary_path, |file| file.write_all(summary_json.as_bytes()))?;
+            if let Some(metrics_file) = &config.metrics_file {
+                let metrics_text: String =
+                    metrics::render(live.summary(), live.records(), output_seconds, Utc::now());
+                metrics::write_metrics_file(Path::new(metrics_file), &metrics_text)?;
+            }
+            println!(
+                "{} changed paths: {} parsed, {} rejected, {} removed, {} pid-changes; now {} records",
+                changed_paths.len(),
+                counts.parsed_count,
+                counts.rejected_count,
+                counts.removed_count,
+                counts.pid_changes,
+                live.summary().record_count
+            );
+            Ok(())
+        },
+    )
+}
+
+// the serve-mode banner; the endpoints are listed at `/`
+fn print_serving(server: &ResultsServer, results_label: &str) {
+    match server.local_addr() {
+        Some(address) => println!(
+            "serving ``{}`` at http://{}/ (Ctrl-C to stop)",
+            results_label, address
+        ),
+        None => println!("serving ``{}`` (Ctrl-C to stop)", results_label),
+    }
+}
+
+/*  -----------------------------------------------------------------
+    The cli-definition.
+    -----------------------------------------------------------------
+*/
+fn cli() -> Command {
+    let about_text = r#"Info...
+  - Walks `source_dir_path` and creates `(output_dir_path)/tracker_output.csv`.
+  - Logs to console; default log-level is 'warn'; use `export LOG_LEVEL="debug"` or "info" (or "trace") to see more output.
+  - Per-module levels work too, eg `export LOG_LEVEL="warn,parse_ocr_tracker::helper=debug"`.
+  - Use `--log_file` (or `export LOG_FILE="path"`) to log to a size-rotated file instead.
+  - Use `--log_format json` (or `export LOG_FORMAT="json"`) for one json-object per log-line.
+  - All options, plus the file-name suffixes, pid-url base, timezone, and output-filename pattern,
+    can be set in a toml file via `--config`; cli-flags override the file.
+  - Useful json is returned with paths, counts, error-filepaths, and the effective config.
+  - With `--serve`, the results are then served over http for quick questions; see the README.
+  - With `--watch`, the csv and summary-json are then kept current as trackers change, until Ctrl-C."#;
+    Command::new("parse_ocr_tracker")
+        .version(GIT_COMMIT)
+        .about(about_text)
+        .arg(arg!(-c --config <PATH> "toml file with run-options"))
+        .arg(arg!(-s --source_dir_path <VALUE>))
+        .arg(arg!(-o --output_dir_path <VALUE>))
+        .arg(arg!(--log_level <LEVEL> "trace, debug, info, warn, error, or directives like `warn,parse_ocr_tracker::helper=debug`; overrides the config-file and `LOG_LEVEL`"))
+        .arg(arg!(--log_format <FORMAT> "text or json; overrides the config-file and `LOG_FORMAT`"))
+        .arg(arg!(--log_file <PATH> "log to this file instead of the console; overrides `LOG_FILE`"))
+        .arg(
+            arg!(--log_max_bytes <BYTES> "rotate the log-file at this size; 0 disables; default 10MB")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            arg!(--log_backup_count <N> "rotated log-files to keep; default 5")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(arg!(--log_warnings_to_stderr "when logging to a file, also print warnings to stderr"))
+        .arg(
+            arg!(--dry_run "only walk and inventory `source_dir_path`; no json-parsing, no csv")
+                .alias("dry-run"),
+        )
+        .arg(arg!(--serve <ADDRESS> "after the run, serve its results over http, eg `127.0.0.1:8080`, until Ctrl-C"))
+        .arg(arg!(--serve_csv_path <PATH> "with `--serve`, serve this earlier run's csv instead of scanning"))
+        .arg(arg!(--watch "after the run, keep the csv and a summary-json current as trackers change, until Ctrl-C"))
+        .arg(
+            arg!(--watch_debounce_seconds <SECONDS> "with `--watch`, batch changes over this interval; default 5")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            arg!(--metrics_file <PATH> "write Prometheus text-format metrics here, eg for the node-exporter textfile-collector")
+                .alias("metrics-file"),
+        )
+        .arg(arg!(--resume "continue from the checkpoint in `output_dir_path`"))
+        .arg(
+            arg!(--checkpoint_every <N> "files between checkpoints; 0 disables; default 10000")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            arg!(--threads <N> "size of the worker thread-pool; default is one per cpu")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            arg!(--max_concurrent_reads <N> "cap on tracker-files open at once")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            arg!(--files_per_second <RATE> "rate-limit on tracker-file reads")
+                .value_parser(clap::value_parser!(f64)),
+        )
+        .arg(arg!(--pid_resolver_url <URL> "search-api url, with `{id}` for the item-key, to look up pids missing from the ingest-trackers"))
+        .arg(arg!(--pid_resolver_cache_path <PATH> "json cache of resolved pids; default `(output_dir_path)/pid_resolver_cache.json`"))
+        .arg(
+            arg!(--pid_resolver_concurrency <N> "pid-resolver requests in flight at once; default 4")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            arg!(--reviews_path <PATH> "csv of review-outcomes (key, pid, status, reviewer, date) to join onto the records")
+                .alias("reviews"),
+        )
+        .arg(
+            arg!(--reviewed_items <MODE> "keep, downrank (to the end of the csv), or exclude reviewed records; default downrank")
+                .value_parser(["keep", "downrank", "exclude"]),
+        )
+        .arg(arg!(--strict "leave records with implausible ocr-statistics out of the csv, instead of flagging them"))
+        .arg(arg!(--timezone <TZ> "IANA name (eg `America/Chicago`) or `UTC`; default `US/Eastern`"))
+        .arg(arg!(--datetime_format <FORMAT> "strftime-format for the json datestamp"))
+        .arg(arg!(--filename_datetime_format <FORMAT> "strftime-format for the csv file-name datestamp"))
+        .arg(
+            arg!(--extra_columns <FIELDS> "comma-separated ocr-tracker fields to add as csv-columns, eg `engine_version,dpi`")
+                .value_delimiter(',')
+                .alias("extra-columns"),
+        )
+}
+
+/*  -----------------------------------------------------------------
+    The output-json for a failed run: `"status": "failed"`, plus the `error` object.
+    If the scan had finished (eg the csv couldn't be written), the usual counts are included too.
+    -----------------------------------------------------------------
+*/
+fn prepare_failure_json(
+    error: &TrackerError, state: RunState, start_instant: Instant, datestamp_time: DateTime<Utc>,
+) -> String {
+    let config: Config = state.config.unwrap_or_default();
+    // -- the echoed config may be the one that failed validation, so its settings can't be trusted here
+    let timezone: Tz = config.timezone().unwrap_or(Tz::UTC);
+    let datetime_format: String = if config::is_valid_strftime(&config.datetime_format) {
+        config.datetime_format.clone()
+    } else {
+        Config::default().datetime_format
+    };
+    let formatted_date_time: String =
+        datestamp_time.with_timezone(&timezone).format(&datetime_format).to_string();
+    let mut map: IndexMap<String, Value> = match &state.results {
+        Some(results) => helper::prepare_summary_map(
+            &results.summary,
+            &results.error_tracker_paths,
+            None,
+            start_instant,
+            formatted_date_time,
+            &config,
+        ),
+        None => helper::prepare_minimal_summary_map(start_instant, formatted_date_time, &config),
+    };
+    helper::insert_error(&mut map, error);
+    serde_json::to_string_pretty(&map).unwrap_or_else(|e| format!("Error serializing output-JSON: {}", e))
 }
 
 // let zz: () = the_var; // for reference -- hack to inspect the type of the_var
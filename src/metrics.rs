@@ -0,0 +1,158 @@
+use crate::error::TrackerError;
+use crate::helper::{self, Record};
+use crate::scanner::{ScanStatus, ScanSummary};
+use chrono::{DateTime, Utc};
+use std::{fmt::Write as _, io::Write as _, path::Path};
+
+/*  -----------------------------------------------------------------
+    The optional metrics-file: the run's counts and timings in the Prometheus text-format, for the
+    node-exporter's textfile-collector.
+    - Everything is a gauge, except `avg_confidence`, a histogram of the records' average confidence.
+    - Every metric is prefixed `parse_ocr_tracker_`.
+    - Written to a temp-file and renamed, so the collector never reads a partial file.
+    -----------------------------------------------------------------
+*/
+const PREFIX: &str = "parse_ocr_tracker_";
+const CONFIDENCE_BUCKETS: [f64; 5] = [30.0, 60.0, 80.0, 90.0, 95.0]; // upper bounds; `+Inf` is added
+
+/// A run's timings that aren't scan-stages, like saving the csv; shown with the stage-durations.
+#[derive(Clone, Debug, Default)]
+pub struct OutputSeconds {
+    pub save_csv: f64,
+    pub prepare_json: f64,
+}
+
+/*  -----------------------------------------------------------------
+    Renders the metrics for a finished (or interrupted) scan.
+    `records` are the records that went into the csv.
+    -----------------------------------------------------------------
+*/
+pub fn render<'a>(
+    summary: &ScanSummary, records: impl IntoIterator<Item = &'a Record>, output_seconds: &OutputSeconds,
+    finished_at: DateTime<Utc>,
+) -> String {
+    let mut text = String::new();
+    gauge(
+        &mut text,
+        "last_run_timestamp_seconds",
+        "When the run finished, as unix-time.",
+        &[("", finished_at.timestamp() as f64)],
+    );
+    let complete: f64 = if summary.status == ScanStatus::Complete {
+        1.0
+    } else {
+        0.0
+    };
+    gauge(
+        &mut text,
+        "last_run_complete",
+        "1 if the run finished; 0 if a Ctrl-C / SIGTERM stopped it early.",
+        &[("", complete)],
+    );
+    gauge(
+        &mut text,
+        "discovered_files",
+        "Files found in the source-dir, by category.",
+        &[
+            (r#"category="ocr_tracker""#, summary.ocr_tracker_count as f64),
+            (
+                r#"category="ingest_tracker""#,
+                summary.ingest_tracker_count as f64,
+            ),
+            (r#"category="error_tracker""#, summary.error_tracker_count as f64),
+            (r#"category="other""#, summary.other_count as f64),
+        ],
+    );
+    gauge(
+        &mut text,
+        "walk_errors",
+        "Paths the walk couldn't get into.",
+        &[("", summary.walk_errors.len() as f64)],
+    );
+
+    // -- one pass over the records, for the missing pids and the confidence-histogram
+    let mut missing_pid_count: usize = 0;
+    let mut bucket_counts = [0_usize; CONFIDENCE_BUCKETS.len()];
+    let (mut confidence_sum, mut record_count) = (0.0_f64, 0_usize);
+    for record in records {
+        if record.pid.is_none() {
+            missing_pid_count += 1;
+        }
+        for (bound, count) in CONFIDENCE_BUCKETS.iter().zip(bucket_counts.iter_mut()) {
+            if record.avg_confidence <= *bound {
+                *count += 1; // buckets are cumulative
+            }
+        }
+        confidence_sum += record.avg_confidence;
+        record_count += 1;
+    }
+    gauge(
+        &mut text,
+        "records",
+        "Ocr-trackers parsed into csv-records.",
+        &[("", record_count as f64)],
+    );
+    gauge(
+        &mut text,
+        "rejected_files",
+        "Ocr-tracker-named files that didn't parse; usually organization-files.",
+        &[("", summary.rejected_count as f64)],
+    );
+    gauge(
+        &mut text,
+        "unprocessed_files",
+        "Ocr-trackers left unparsed by an interrupted run.",
+        &[("", summary.unprocessed_count as f64)],
+    );
+    gauge(
+        &mut text,
+        "missing_pids",
+        "Records with no pid, from either the ingest-trackers or the pid-resolver.",
+        &[("", missing_pid_count as f64)],
+    );
+
+    let stages = &summary.stage_seconds;
+    gauge(
+        &mut text,
+        "stage_duration_seconds",
+        "Elapsed seconds per run-stage.",
+        &[
+            (r#"stage="find_json_files""#, stages.find_json_files),
+            (r#"stage="make_id_to_pid_map""#, stages.make_id_to_pid_map),
+            (r#"stage="resolve_pids""#, stages.resolve_pids),
+            (r#"stage="process_files""#, stages.process_files),
+            (r#"stage="save_csv""#, output_seconds.save_csv),
+            (r#"stage="prepare_json""#, output_seconds.prepare_json),
+        ],
+    );
+
+    // -- the histogram
+    let name = format!("{}avg_confidence", PREFIX);
+    let _ = writeln!(text, "# HELP {} The records' average ocr-confidence.", name);
+    let _ = writeln!(text, "# TYPE {} histogram", name);
+    for (bound, count) in CONFIDENCE_BUCKETS.iter().zip(bucket_counts) {
+        let _ = writeln!(text, "{}_bucket{{le=\"{}\"}} {}", name, bound, count);
+    }
+    let _ = writeln!(text, "{}_bucket{{le=\"+Inf\"}} {}", name, record_count);
+    let _ = writeln!(text, "{}_sum {}", name, confidence_sum);
+    let _ = writeln!(text, "{}_count {}", name, record_count);
+    text
+}
+
+/// Writes the rendered metrics to `path`, atomically.
+pub fn write_metrics_file(path: &Path, text: &str) -> Result<(), TrackerError> {
+    helper::write_atomically(path, |file| file.write_all(text.as_bytes()))
+}
+
+// one gauge, with a sample per label-set; an empty label-set is written without braces
+fn gauge(text: &mut String, name: &str, help: &str, samples: &[(&str, f64)]) {
+    let _ = writeln!(text, "# HELP {}{} {}", PREFIX, name, help);
+    let _ = writeln!(text, "# TYPE {}{} gauge", PREFIX, name);
+    for (labels, value) in samples {
+        let _ = if labels.is_empty() {
+            writeln!(text, "{}{} {}", PREFIX, name, value)
+        } else {
+            writeln!(text, "{}{}{{{}}} {}", PREFIX, name, labels, value)
+        };
+    }
+}
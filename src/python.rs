@@ -0,0 +1,286 @@
+/*  -----------------------------------------------------------------
+    Python bindings, built with the `python` feature (see `pyproject.toml`):
+
+        import parse_ocr_tracker
+        scanner = parse_ocr_tracker.Scanner("/path/to/hall_hoag", threads=4)
+        result = scanner.scan()  # or scan(columnar=True) for a dict of lists, eg for `pandas.DataFrame(...)`
+        result["records"], result["summary"], result["rejected_paths"], result["error_paths"], result["id_to_pid_map"]
+
+    The summary is the same map as the cli's returned json (without the csv-path, since nothing is written).
+    The GIL is released while scanning.
+    -----------------------------------------------------------------
+*/
+use crate::error::TrackerError;
+use crate::helper;
+use crate::scanner::{ScanResults, Scanner};
+use chrono::Utc;
+use pyo3::exceptions::{PyOSError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyList, PyString};
+use serde_json::{Map, Value};
+use std::{path::PathBuf, time::Instant};
+
+#[pyclass(name = "Scanner", module = "parse_ocr_tracker", frozen)]
+struct PyScanner {
+    inner: Scanner,
+}
+
+#[pymethods]
+impl PyScanner {
+    #[new]
+    #[pyo3(signature = (
+        source_dir_path, *, ocr_tracker_suffix=None, ingest_tracker_suffix=None, error_tracker_marker=None,
+        pid_url_base=None, threads=None, max_concurrent_reads=None, files_per_second=None, extra_columns=None
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        source_dir_path: String, ocr_tracker_suffix: Option<String>, ingest_tracker_suffix: Option<String>,
+        error_tracker_marker: Option<String>, pid_url_base: Option<String>, threads: Option<usize>,
+        max_concurrent_reads: Option<usize>, files_per_second: Option<f64>,
+        extra_columns: Option<Vec<String>>,
+    ) -> PyResult<Self> {
+        let mut builder = Scanner::builder(source_dir_path);
+        if let Some(suffix) = ocr_tracker_suffix {
+            builder = builder.ocr_tracker_suffix(suffix);
+        }
+        if let Some(suffix) = ingest_tracker_suffix {
+            builder = builder.ingest_tracker_suffix(suffix);
+        }
+        if let Some(marker) = error_tracker_marker {
+            builder = builder.error_tracker_marker(marker);
+        }
+        if let Some(base) = pid_url_base {
+            builder = builder.pid_url_base(base);
+        }
+        if let Some(threads) = threads {
+            builder = builder.threads(threads);
+        }
+        if let Some(max_reads) = max_concurrent_reads {
+            builder = builder.max_concurrent_reads(max_reads);
+        }
+        if let Some(rate) = files_per_second {
+            builder = builder.files_per_second(rate);
+        }
+        if let Some(columns) = extra_columns {
+            builder = builder.extra_columns(columns);
+        }
+        let inner: Scanner = builder.build()?;
+        Ok(PyScanner { inner })
+    }
+
+    /// Walks and classifies the source-dir without reading any files; returns a dict of path-lists.
+    fn discover<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let paths = py.detach(|| self.inner.discover())?;
+        let dict = PyDict::new(py);
+        dict.set_item("ocr_tracker_paths", path_strings(&paths.ocr_tracker_paths))?;
+        dict.set_item("ingest_tracker_paths", path_strings(&paths.ingest_tracker_paths))?;
+        dict.set_item("error_tracker_paths", path_strings(&paths.error_tracker_paths))?;
+        dict.set_item("other_paths", path_strings(&paths.other_paths))?;
+        let walk_errors: Value = serde_json::to_value(&paths.walk_errors)
+            .map_err(|e| PyRuntimeError::new_err(format!("Failed to convert walk-errors: {}", e)))?;
+        dict.set_item("walk_errors", value_to_py(py, &walk_errors)?)?;
+        Ok(dict)
+    }
+
+    /// Runs discovery, id-to-pid mapping and record parsing.
+    #[pyo3(signature = (*, columnar=false))]
+    fn scan<'py>(&self, py: Python<'py>, columnar: bool) -> PyResult<Bound<'py, PyDict>> {
+        let start_instant = Instant::now();
+        let results: ScanResults = py.detach(|| self.inner.scan())?;
+
+        // -- records; as row-dicts, or as one list per column; either way, with the csv's columns
+        let config = self.inner.config();
+        let records: Value = Value::Array(
+            results
+                .records
+                .iter()
+                .map(|record| Value::Object(helper::csv_row(record, &config.extra_columns)))
+                .collect(),
+        );
+        let records: Value = if columnar { to_columns(records) } else { records };
+
+        // -- the same summary-map the cli prints
+        let timezone = config.timezone()?;
+        let formatted_date_time: String =
+            Utc::now().with_timezone(&timezone).format(&config.datetime_format).to_string();
+        let summary = helper::prepare_summary_map(
+            &results.summary,
+            &results.error_tracker_paths,
+            None,
+            start_instant,
+            formatted_date_time,
+            config,
+        );
+
+        let dict = PyDict::new(py);
+        dict.set_item("records", value_to_py(py, &records)?)?;
+        dict.set_item(
+            "summary",
+            value_to_py(py, &Value::Object(summary.into_iter().collect()))?,
+        )?;
+        dict.set_item("rejected_paths", path_strings(&results.rejected_paths))?;
+        dict.set_item("error_paths", path_strings(&results.error_tracker_paths))?;
+        dict.set_item("id_to_pid_map", results.id_to_pid_map)?;
+        dict.set_item("resolved_pid_map", results.resolved_pid_map)?;
+        Ok(dict)
+    }
+}
+
+#[pymodule]
+fn parse_ocr_tracker(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyScanner>()?;
+    Ok(())
+}
+
+// config-errors raise ValueError; discovery- and read-errors, OSError; the rest, RuntimeError
+impl From<TrackerError> for PyErr {
+    fn from(error: TrackerError) -> PyErr {
+        match error {
+            TrackerError::Config(_) => PyValueError::new_err(error.to_string()),
+            TrackerError::Discovery { .. } | TrackerError::Read { .. } => {
+                PyOSError::new_err(error.to_string())
+            }
+            _ => PyRuntimeError::new_err(error.to_string()),
+        }
+    }
+}
+
+fn path_strings(paths: &[PathBuf]) -> Vec<String> {
+    paths.iter().map(|path| path.to_string_lossy().to_string()).collect()
+}
+
+// `[{"a": 1, "b": 2}, {"a": 3, "b": 4}]` -> `{"a": [1, 3], "b": [2, 4]}`
+fn to_columns(rows: Value) -> Value {
+    let mut columns: Map<String, Value> = Map::new();
+    if let Value::Array(rows) = rows {
+        for row in rows {
+            if let Value::Object(fields) = row {
+                for (name, value) in fields {
+                    let column = columns.entry(name).or_insert_with(|| Value::Array(Vec::new()));
+                    if let Value::Array(values) = column {
+                        values.push(value);
+                    }
+                }
+            }
+        }
+    }
+    Value::Object(columns)
+}
+
+fn value_to_py<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        Value::Null => py.None().into_bound(py),
+        Value::Bool(b) => PyBool::new(py, *b).to_owned().into_any(),
+        Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => i.into_pyobject(py)?.into_any(),
+            (None, Some(u)) => u.into_pyobject(py)?.into_any(),
+            _ => n.as_f64().unwrap_or(f64::NAN).into_pyobject(py)?.into_any(),
+        },
+        Value::String(s) => PyString::new(py, s).into_any(),
+        Value::Array(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(value_to_py(py, item)?)?;
+            }
+            list.into_any()
+        }
+        Value::Object(fields) => {
+            let dict = PyDict::new(py);
+            for (key, item) in fields {
+                dict.set_item(key, value_to_py(py, item)?)?;
+            }
+            dict.into_any()
+        }
+    })
+}
+
+/*  -----------------------------------------------------------------
+    These run the bindings in an embedded interpreter, so they need a libpython to link against:
+        % cargo test --features python --lib python::
+    -----------------------------------------------------------------
+*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{self, FixtureManifest, FixtureSpec};
+    use std::ffi::CStr;
+    use tempfile::TempDir;
+
+    // runs `code` with the module, the fixture-tree's path, its manifest's counts and the csv-columns as globals
+    fn run_python(code: &CStr) {
+        let dir = TempDir::new().unwrap();
+        let manifest: FixtureManifest = fixtures::generate(dir.path(), &FixtureSpec::scaled(2, 40)).unwrap();
+        Python::initialize();
+        Python::attach(|py| {
+            let module = PyModule::new(py, "parse_ocr_tracker").unwrap();
+            parse_ocr_tracker(&module).unwrap();
+            let globals = PyDict::new(py);
+            globals.set_item("parse_ocr_tracker", module).unwrap();
+            globals.set_item("source_dir_path", dir.path().to_string_lossy()).unwrap();
+            globals.set_item("ocr_tracker_count", manifest.ocr_tracker_count).unwrap();
+            globals.set_item("record_count", manifest.record_count).unwrap();
+            globals.set_item("rejected_count", manifest.rejected_count).unwrap();
+            globals.set_item("csv_columns", helper::csv_columns(&[])).unwrap();
+            if let Err(e) = py.run(code, Some(&globals), None) {
+                panic!("{}", e);
+            }
+        });
+    }
+
+    #[test]
+    fn discover_and_scan_as_rows() {
+        run_python(
+            cr#"
+scanner = parse_ocr_tracker.Scanner(source_dir_path, threads=2)
+paths = scanner.discover()
+assert sorted(paths) == ["error_tracker_paths", "ingest_tracker_paths", "ocr_tracker_paths", "other_paths", "walk_errors"]
+assert len(paths["ocr_tracker_paths"]) == ocr_tracker_count
+assert paths["walk_errors"] == []
+
+result = scanner.scan()
+records = result["records"]
+assert len(records) == record_count
+assert len(result["rejected_paths"]) == rejected_count
+assert result["summary"]["ocr_data_vector_count"] == record_count
+assert result["summary"]["tracker_output_csv_path"] is None  # nothing is written
+assert list(records[0]) == csv_columns
+assert isinstance(records[0]["word_count"], int), records[0]
+assert any(r["pid"] for r in records)
+assert all(r["pid"] is None or r["pid"].startswith("bdr:") for r in records), [r["pid"] for r in records]
+"#,
+        );
+    }
+
+    #[test]
+    fn scan_as_columns() {
+        run_python(
+            cr#"
+scanner = parse_ocr_tracker.Scanner(source_dir_path)
+rows = scanner.scan()["records"]
+columns = scanner.scan(columnar=True)["records"]
+assert list(columns) == list(rows[0])
+assert all(len(values) == record_count for values in columns.values())
+assert columns["image_name"] == [row["image_name"] for row in rows]
+"#,
+        );
+    }
+
+    #[test]
+    fn errors_raise_matching_exceptions() {
+        run_python(
+            cr#"
+for bad_options in [{"threads": 0}, {"max_concurrent_reads": 0}, {"files_per_second": -1.0}]:
+    try:
+        parse_ocr_tracker.Scanner(source_dir_path, **bad_options)
+        raise AssertionError(f"no error for {bad_options}")
+    except ValueError:
+        pass
+try:
+    parse_ocr_tracker.Scanner(source_dir_path + "/not_mounted").scan()
+    raise AssertionError("no error for a missing source-dir")
+except OSError as e:
+    assert "not_mounted" in str(e), str(e)
+"#,
+        );
+    }
+}
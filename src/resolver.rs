@@ -0,0 +1,232 @@
+use crate::config::Config;
+use crate::error::TrackerError;
+use crate::helper;
+use crate::shutdown;
+use crate::{log_debug, log_info, log_warn};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+/*  -----------------------------------------------------------------
+    The optional pid-resolver: for items that have an ocr-tracker but no usable ingest-tracker,
+    asks the repository's search-api for the pid.
+    - Off unless `pid_resolver_url` is set; its `{id}` is replaced with the item's key (url-encoded).
+    - The pid is read from the json-response at `pid_resolver_pid_pointer`, a json-pointer.
+    - Found pids are cached on disk, as a json object of key-to-pid, so a rerun doesn't ask again.
+      Not-found keys aren't cached, since those items may be ingested later.
+    - At most `pid_resolver_concurrency` requests are in flight at once.
+    - A failed request is logged and counted; it doesn't fail the run.
+    -----------------------------------------------------------------
+*/
+pub struct PidResolver {
+    url_template: String,
+    pid_pointer: String,
+    cache_path: Option<PathBuf>,
+    concurrency: usize,
+    agent: ureq::Agent,
+}
+
+/// What the pid-resolver found, for the keys it was asked about.
+#[derive(Clone, Debug, Default)]
+pub struct Resolution {
+    pub pids: BTreeMap<String, String>, // key-to-pid, for the keys that were found
+    pub cached_count: usize,            // found in the cache, without a request
+    pub request_count: usize,
+    pub failed_count: usize, // requests that errored (not just not-found)
+}
+
+// what one request found out
+enum Lookup {
+    Found(String),
+    NotFound,
+    Failed(String),
+}
+
+impl PidResolver {
+    /*  -----------------------------------------------------------------
+        Builds the resolver, if `pid_resolver_url` is set.
+        The cache is `pid_resolver_cache_path`, or else `(output_dir_path)/pid_resolver_cache.json`,
+        or else there's no cache.
+        -----------------------------------------------------------------
+    */
+    pub fn from_config(config: &Config) -> Result<Option<PidResolver>, TrackerError> {
+        let Some(url_template) = &config.pid_resolver_url else {
+            return Ok(None);
+        };
+        if !url_template.contains("{id}") {
+            return Err(TrackerError::Config(
+                "`pid_resolver_url` must contain `{id}`".to_string(),
+            ));
+        }
+        if !config.pid_resolver_pid_pointer.is_empty() && !config.pid_resolver_pid_pointer.starts_with('/') {
+            return Err(TrackerError::Config(format!(
+                "`pid_resolver_pid_pointer` ``{}`` must be a json-pointer, like `/response/docs/0/pid`",
+                config.pid_resolver_pid_pointer
+            )));
+        }
+        if config.pid_resolver_concurrency == 0 {
+            return Err(TrackerError::Config(
+                "`pid_resolver_concurrency` must be at least 1".to_string(),
+            ));
+        }
+        if config.pid_resolver_timeout_seconds == 0 {
+            return Err(TrackerError::Config(
+                "`pid_resolver_timeout_seconds` must be at least 1".to_string(),
+            ));
+        }
+        let cache_path: Option<PathBuf> = match (&config.pid_resolver_cache_path, &config.output_dir_path) {
+            (Some(cache_path), _) => Some(PathBuf::from(cache_path)),
+            (None, Some(output_dir)) => Some(Path::new(output_dir).join("pid_resolver_cache.json")),
+            (None, None) => None,
+        };
+        let agent: ureq::Agent = ureq::Agent::config_builder()
+            .timeout_global(Some(Duration::from_secs(config.pid_resolver_timeout_seconds)))
+            .build()
+            .into();
+        Ok(Some(PidResolver {
+            url_template: url_template.clone(),
+            pid_pointer: config.pid_resolver_pid_pointer.clone(),
+            cache_path,
+            concurrency: config.pid_resolver_concurrency,
+            agent,
+        }))
+    }
+
+    /*  -----------------------------------------------------------------
+        Looks up each key: first in the cache, then, `concurrency` at a time, via the search-api.
+        Stops sending new requests once a Ctrl-C / SIGTERM is received.
+        -----------------------------------------------------------------
+    */
+    pub fn resolve(&self, keys: &BTreeSet<String>) -> Resolution {
+        let mut cache: BTreeMap<String, String> = self.load_cache();
+        let mut resolution = Resolution::default();
+        let mut uncached_keys: Vec<&String> = Vec::new();
+        for key in keys {
+            match cache.get(key) {
+                Some(pid) => {
+                    resolution.pids.insert(key.clone(), pid.clone());
+                    resolution.cached_count += 1;
+                }
+                None => uncached_keys.push(key),
+            }
+        }
+
+        // -- ask the api about the rest, from `concurrency` worker-threads
+        let next_index = AtomicUsize::new(0);
+        let lookups: Mutex<Vec<(String, Lookup)>> = Mutex::new(Vec::new());
+        thread::scope(|scope| {
+            for _ in 0..self.concurrency.min(uncached_keys.len()) {
+                scope.spawn(|| loop {
+                    let index: usize = next_index.fetch_add(1, Ordering::SeqCst);
+                    if index >= uncached_keys.len() || shutdown::requested() {
+                        break;
+                    }
+                    let key: &String = uncached_keys[index];
+                    let lookup = self.request_pid(key);
+                    lookups.lock().unwrap_or_else(|e| e.into_inner()).push((key.clone(), lookup));
+                });
+            }
+        });
+
+        // -- tally, and add the new finds to the cache
+        let lookups: Vec<(String, Lookup)> = lookups.into_inner().unwrap_or_else(|e| e.into_inner());
+        resolution.request_count = lookups.len();
+        for (key, lookup) in lookups {
+            match lookup {
+                Lookup::Found(pid) => {
+                    cache.insert(key.clone(), pid.clone());
+                    resolution.pids.insert(key, pid);
+                }
+                Lookup::NotFound => log_debug!(item_key = key.as_str(); "pid-resolver found no pid"),
+                Lookup::Failed(message) => {
+                    log_warn!(item_key = key.as_str(), error = message.as_str(); "pid-resolver request failed");
+                    resolution.failed_count += 1;
+                }
+            }
+        }
+        if resolution.pids.len() > resolution.cached_count {
+            self.save_cache(&cache); // only if there's something new
+        }
+        log_info!(
+            "pid-resolver: {} keys asked about; {} found ({} from the cache); {} requests failed",
+            keys.len(),
+            resolution.pids.len(),
+            resolution.cached_count,
+            resolution.failed_count
+        );
+        resolution
+    }
+
+    // one request; a 404 counts as not-found, like an empty search-result
+    fn request_pid(&self, key: &str) -> Lookup {
+        let url: String = self.url_template.replace("{id}", &url_encode(key));
+        let body: String = match self.agent.get(&url).call() {
+            Ok(mut response) => match response.body_mut().read_to_string() {
+                Ok(body) => body,
+                Err(e) => {
+                    return Lookup::Failed(format!("Failed to read the response from ``{}``: {}", url, e))
+                }
+            },
+            Err(ureq::Error::StatusCode(404)) => return Lookup::NotFound,
+            Err(e) => return Lookup::Failed(format!("Failed to get ``{}``: {}", url, e)),
+        };
+        let response: Value = match serde_json::from_str(&body) {
+            Ok(response) => response,
+            Err(e) => return Lookup::Failed(format!("Failed to parse the response from ``{}``: {}", url, e)),
+        };
+        match response.pointer(&self.pid_pointer).and_then(Value::as_str).map(str::trim) {
+            Some(pid) if !pid.is_empty() => Lookup::Found(pid.to_string()),
+            _ => Lookup::NotFound,
+        }
+    }
+
+    // a missing cache is normal; an unreadable one is logged and ignored, since it's only a cache
+    fn load_cache(&self) -> BTreeMap<String, String> {
+        let Some(cache_path) = &self.cache_path else {
+            return BTreeMap::new();
+        };
+        let loaded: Result<BTreeMap<String, String>, String> = match fs::read(cache_path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| e.to_string()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(e.to_string()),
+        };
+        loaded.unwrap_or_else(|message| {
+            log_warn!(cache_path:% = cache_path.display(), error = message.as_str(); "ignoring the pid-resolver cache");
+            BTreeMap::new()
+        })
+    }
+
+    // written atomically, so an interrupted write can't leave a truncated cache
+    fn save_cache(&self, cache: &BTreeMap<String, String>) {
+        let Some(cache_path) = &self.cache_path else {
+            return;
+        };
+        let saved = helper::write_atomically(cache_path, |file| {
+            serde_json::to_writer_pretty(file, cache).map_err(io::Error::other)
+        });
+        if let Err(e) = saved {
+            log_warn!(cache_path:% = cache_path.display(), error:% = e; "couldn't save the pid-resolver cache");
+        }
+    }
+}
+
+// percent-encodes everything but the url-safe "unreserved" characters
+fn url_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
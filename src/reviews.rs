@@ -0,0 +1,185 @@
+use crate::error::TrackerError;
+use crate::helper::{self, Record};
+use crate::log_info;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+/*  -----------------------------------------------------------------
+    Human review outcomes, merged back onto the records so already-checked items stop being surfaced.
+    - Read from a csv with the columns `key`, `pid`, `status`, `reviewer`, `date`; a row needs `status`,
+      and a `key` (eg `HH001545_0001`) or a `pid` (eg `bdr:1001`). Other columns are ignored.
+    - Any status counts as reviewed (eg "ok", "needs-reocr", "needs-rotation"); the status is passed through.
+    - A record is matched by its key first, then by its pid. If an item has several rows, the last one wins.
+    - Reviewed records are then kept in place, moved to the end of the csv, or left out; see `ReviewedItems`.
+    -----------------------------------------------------------------
+*/
+pub struct ReviewSet {
+    by_key: HashMap<String, Review>,
+    by_pid: HashMap<String, Review>,
+}
+
+/// One row of the reviews-csv.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Review {
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub key: Option<String>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub pid: Option<String>,
+    pub status: String,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub reviewer: Option<String>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub date: Option<String>,
+}
+
+/// What happens to reviewed records: kept in place, moved after the unreviewed ones, or left out of the csv.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewedItems {
+    Keep,
+    #[default]
+    Downrank,
+    Exclude,
+}
+
+/// The records after the reviews are joined, split into the ones that stay in the csv and the excluded ones.
+#[derive(Clone, Debug, Default)]
+pub struct ReviewedRecords {
+    pub records: Vec<Record>,
+    pub record_paths: Vec<PathBuf>,
+    pub excluded_records: Vec<Record>, // only with `ReviewedItems::Exclude`
+    pub excluded_record_paths: Vec<PathBuf>,
+    pub reviewed_count: usize, // records with a review, excluded or not
+    pub coverage: BTreeMap<String, ReviewCoverage>,
+}
+
+/// Per-collection review-coverage, for the summary-json.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ReviewCoverage {
+    pub item_count: usize,
+    pub reviewed_count: usize,
+    pub status_counts: BTreeMap<String, usize>,
+}
+
+impl ReviewSet {
+    pub fn from_csv(csv_path: &Path) -> Result<ReviewSet, TrackerError> {
+        let read_error = |e: csv::Error| TrackerError::Read {
+            path: csv_path.to_path_buf(),
+            source: e.into(),
+        };
+        let file = File::open(csv_path).map_err(|e| TrackerError::Read {
+            path: csv_path.to_path_buf(),
+            source: e,
+        })?;
+        let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(file);
+        let mut reviews = ReviewSet {
+            by_key: HashMap::new(),
+            by_pid: HashMap::new(),
+        };
+        for (index, result) in reader.deserialize::<Review>().enumerate() {
+            let review: Review = result.map_err(read_error)?;
+            if review.key.is_none() && review.pid.is_none() {
+                return Err(TrackerError::Config(format!(
+                    "reviews-csv ``{}``, row {}: needs a `key` or a `pid`",
+                    csv_path.display(),
+                    index + 2 // 1-based, after the header-row
+                )));
+            }
+            if let Some(key) = &review.key {
+                reviews.by_key.insert(key.clone(), review.clone());
+            }
+            if let Some(pid) = &review.pid {
+                reviews.by_pid.insert(pid.clone(), review.clone());
+            }
+        }
+        log_info!(
+            "reviews loaded from {:?}: {} keys, {} pids",
+            csv_path,
+            reviews.by_key.len(),
+            reviews.by_pid.len()
+        );
+        Ok(reviews)
+    }
+
+    /// Sets the record's review-columns from its review, if it has one; returns whether it does.
+    pub fn join(&self, record: &mut Record, item_key: &str) -> bool {
+        let review: Option<&Review> =
+            self.by_key.get(item_key).or_else(|| record.pid.as_ref().and_then(|pid| self.by_pid.get(pid)));
+        record.review_status = review.map(|r| r.status.clone());
+        record.reviewer = review.and_then(|r| r.reviewer.clone());
+        record.review_date = review.and_then(|r| r.date.clone());
+        review.is_some()
+    }
+
+    /*  -----------------------------------------------------------------
+        Joins each record to its review, tallies the coverage, then keeps, downranks or excludes the
+        reviewed records. Downranking is a stable move to the end, so both groups keep their path-order.
+        -----------------------------------------------------------------
+    */
+    pub fn apply(
+        &self, records: Vec<Record>, record_paths: Vec<PathBuf>, reviewed_items: ReviewedItems,
+    ) -> ReviewedRecords {
+        let mut keyed_records: Vec<(String, PathBuf, Record)> = Vec::with_capacity(records.len());
+        for (mut record, path) in records.into_iter().zip(record_paths) {
+            let item_key: String = helper::parse_key_from_path(&path);
+            self.join(&mut record, &item_key);
+            keyed_records.push((item_key, path, record));
+        }
+        let coverage = coverage(keyed_records.iter().map(|(key, _, record)| (key.as_str(), record)));
+        let mut reviewed = ReviewedRecords {
+            reviewed_count: coverage.values().map(|c| c.reviewed_count).sum(),
+            coverage,
+            ..ReviewedRecords::default()
+        };
+        let mut downranked: Vec<(PathBuf, Record)> = Vec::new();
+        for (_, path, record) in keyed_records {
+            match (record.review_status.is_some(), reviewed_items) {
+                (true, ReviewedItems::Exclude) => {
+                    reviewed.excluded_record_paths.push(path);
+                    reviewed.excluded_records.push(record);
+                }
+                (true, ReviewedItems::Downrank) => downranked.push((path, record)),
+                _ => {
+                    reviewed.record_paths.push(path);
+                    reviewed.records.push(record);
+                }
+            }
+        }
+        for (path, record) in downranked {
+            reviewed.record_paths.push(path);
+            reviewed.records.push(record);
+        }
+        reviewed
+    }
+}
+
+/*  -----------------------------------------------------------------
+    Tallies review-coverage per collection, from each record's key and its (already joined) review-columns.
+    The collection is the key's part before the first `_`, eg `HH001545` for `HH001545_0001`.
+    -----------------------------------------------------------------
+*/
+pub fn coverage<'a>(
+    keyed_records: impl IntoIterator<Item = (&'a str, &'a Record)>,
+) -> BTreeMap<String, ReviewCoverage> {
+    let mut coverage: BTreeMap<String, ReviewCoverage> = BTreeMap::new();
+    for (item_key, record) in keyed_records {
+        let collection: &str = item_key.split('_').next().unwrap_or(item_key);
+        let entry: &mut ReviewCoverage = coverage.entry(collection.to_string()).or_default();
+        entry.item_count += 1;
+        if let Some(status) = &record.review_status {
+            entry.reviewed_count += 1;
+            *entry.status_counts.entry(status.clone()).or_default() += 1;
+        }
+    }
+    coverage
+}
+
+// a blank csv-cell is a missing value
+fn empty_as_none<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    let value: Option<String> = Option::deserialize(deserializer)?;
+    Ok(value.filter(|v| !v.is_empty()))
+}
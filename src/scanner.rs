@@ -0,0 +1,507 @@
+use crate::checkpoint::Checkpoint;
+use crate::config::Config;
+use crate::error::TrackerError;
+use crate::helper::{self, DiscoveredPaths, IngestInfo, IngestTrackers, Record, WalkError};
+use crate::resolver::{PidResolver, Resolution};
+use crate::reviews::{ReviewCoverage, ReviewSet, ReviewedItems, ReviewedRecords};
+use crate::schema::{self, SchemaVersion};
+use crate::shutdown;
+use crate::throttle::IoThrottle;
+use crate::validation::{self, ValidatedRecords, ValidationWarning};
+use crate::{log_debug, log_info, log_warn};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+/// Walks a tree of tracker files and parses the ocr-trackers into [`Record`]s.
+///
+/// A scan runs three stages:
+/// 1. [`find_json_files`](crate::helper::find_json_files) classifies every file by name
+///    (ocr-tracker, ingest-tracker, error-tracker, or other).
+/// 2. [`read_ingest_trackers`](crate::helper::read_ingest_trackers) reads the ingest-trackers into an
+///    item-key-to-pid map, plus a map of the items' other ingest-fields.
+/// 3. [`process_files`](crate::helper::process_files) parses the ocr-trackers, joining each to its pid;
+///    then each record gets its item's ingest-fields.
+///
+/// With a `pid_resolver_url`, the items that have no pid after stage 2 are looked up via the
+/// [`PidResolver`](crate::resolver::PidResolver) before stage 3.
+///
+/// With a `reviews_path`, each record is then joined to its review-outcome, and reviewed records
+/// are kept, moved to the end, or excluded (see [`ReviewSet`](crate::reviews::ReviewSet)).
+///
+/// ```no_run
+/// use parse_ocr_tracker::Scanner;
+///
+/// let scanner = Scanner::builder("/path/to/hall_hoag").threads(4).build()?;
+/// let results = scanner.scan()?;
+/// println!("{} records; {} rejected", results.summary.record_count, results.summary.rejected_count);
+/// # Ok::<(), parse_ocr_tracker::TrackerError>(())
+/// ```
+pub struct Scanner {
+    config: Config,
+    io_throttle: IoThrottle,
+    thread_pool: Option<ThreadPool>, // only when `threads` is set; otherwise rayon's global pool
+    pid_resolver: Option<PidResolver>, // only when `pid_resolver_url` is set
+    reviews: Option<ReviewSet>,      // only when `reviews_path` is set
+}
+
+/// Builds a [`Scanner`]; every option defaults to the [`Config`] default.
+pub struct ScannerBuilder {
+    config: Config,
+}
+
+/// What a scan produced: the parsed records, the paths that weren't parsed, and a [`ScanSummary`].
+#[derive(Clone, Debug)]
+pub struct ScanResults {
+    pub records: Vec<Record>,
+    pub record_paths: Vec<PathBuf>, // each record's ocr-tracker, in the same order
+    pub excluded_records: Vec<Record>, // reviewed records left out, with `reviewed_items = "exclude"`
+    pub excluded_record_paths: Vec<PathBuf>,
+    pub invalid_records: Vec<Record>, // records that failed a validation-check, left out with `strict`
+    pub invalid_record_paths: Vec<PathBuf>,
+    pub rejected_paths: Vec<PathBuf>, // ocr-tracker-named files that didn't parse -- usually organization-files
+    pub ingest_tracker_paths: Vec<PathBuf>,
+    pub error_tracker_paths: Vec<PathBuf>,
+    pub other_paths: Vec<PathBuf>,
+    pub id_to_pid_map: BTreeMap<String, String>,
+    pub resolved_pid_map: BTreeMap<String, String>, // from the pid-resolver, for keys missing from the map above
+    pub ingest_info_map: BTreeMap<String, IngestInfo>, // the ingest-trackers' other fields, for items that have any
+    pub summary: ScanSummary,
+}
+
+/// Counts and timings for a scan.
+#[derive(Clone, Debug, Serialize)]
+pub struct ScanSummary {
+    pub status: ScanStatus,
+    pub source_dir_path: String,
+    pub ocr_tracker_count: usize,
+    pub ingest_tracker_count: usize,
+    pub error_tracker_count: usize,
+    pub other_count: usize,
+    pub walk_errors: Vec<WalkError>, // paths the walk couldn't get into; anything under them is missing
+    pub pid_count: usize,            // entries in the id-to-pid map
+    pub resolved_pid_count: usize,   // entries in the resolved-pid map
+    pub resolver_failed_count: usize,
+    pub reviewed_count: usize, // records with a review-outcome, excluded or not
+    pub review_excluded_count: usize, // reviewed records left out of `records`
+    pub review_coverage: BTreeMap<String, ReviewCoverage>, // per collection
+    pub record_count: usize,
+    pub validation_failures: BTreeMap<ValidationWarning, usize>, // records per failed check
+    pub validation_rejected_count: usize, // failing records left out of `records`; only with `strict`
+    pub schema_version_counts: BTreeMap<SchemaVersion, usize>, // records per ocr-tracker version
+    pub extra_field_counts: BTreeMap<String, usize>, // records per ocr-tracker field outside the Record's own
+    pub rejected_count: usize,
+    pub unprocessed_count: usize, // non-zero only if the scan was interrupted
+    pub resumed_count: usize,     // results reused from a checkpoint
+    pub thread_count: usize,
+    pub stage_seconds: StageSeconds,
+}
+
+/// Whether the scan finished, or was stopped early by a Ctrl-C / SIGTERM.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanStatus {
+    Complete,
+    Interrupted,
+}
+
+/// Elapsed seconds per scan-stage.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct StageSeconds {
+    pub find_json_files: f64,
+    pub make_id_to_pid_map: f64,
+    pub resolve_pids: f64,
+    pub process_files: f64,
+}
+
+impl ScanStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScanStatus::Complete => "complete",
+            ScanStatus::Interrupted => "interrupted",
+        }
+    }
+
+    /*  -----------------------------------------------------------------
+        Interrupted only if a signal actually cut the scan short: the walk quit early, or some
+        ocr-trackers were left unparsed. A signal that lands after the last file was parsed doesn't count.
+        -----------------------------------------------------------------
+    */
+    fn from_skipped_work(walk_interrupted: bool, unprocessed_count: usize) -> ScanStatus {
+        if walk_interrupted || unprocessed_count > 0 {
+            ScanStatus::Interrupted
+        } else {
+            ScanStatus::Complete
+        }
+    }
+}
+
+impl Scanner {
+    /// Starts a builder for scanning `source_dir_path`.
+    pub fn builder(source_dir_path: impl Into<String>) -> ScannerBuilder {
+        let config = Config {
+            source_dir_path: Some(source_dir_path.into()),
+            ..Config::default()
+        };
+        ScannerBuilder { config }
+    }
+
+    /// Builds a scanner from a full run-config; only the source-dir, classification, pid-url,
+    /// pid-resolver, reviews and concurrency settings are used.
+    pub fn from_config(config: &Config) -> Result<Scanner, TrackerError> {
+        ScannerBuilder {
+            config: config.clone(),
+        }
+        .build()
+    }
+
+    /// The options this scanner was built with.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn source_dir_path(&self) -> &str {
+        self.config.source_dir_path.as_deref().unwrap_or_default()
+    }
+
+    /// The review-outcomes loaded from `reviews_path`, if it's set.
+    pub fn reviews(&self) -> Option<&ReviewSet> {
+        self.reviews.as_ref()
+    }
+
+    /// The read-limits shared by every stage.
+    pub fn io_throttle(&self) -> &IoThrottle {
+        &self.io_throttle
+    }
+
+    /// Runs `op` on the scanner's thread-pool, so other parallel work honors `threads` too.
+    pub fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        match &self.thread_pool {
+            Some(pool) => pool.install(op),
+            None => op(),
+        }
+    }
+
+    /// Walks the source-dir and classifies the files, without reading any of them.
+    pub fn discover(&self) -> Result<DiscoveredPaths, TrackerError> {
+        check_source_dir(Path::new(self.source_dir_path()))?;
+        Ok(self.install(|| helper::find_json_files(self.source_dir_path(), &self.config)))
+    }
+
+    /// Runs all three stages.
+    pub fn scan(&self) -> Result<ScanResults, TrackerError> {
+        self.scan_with_checkpoint(None)
+    }
+
+    /// Runs all three stages; with a checkpoint, reuses its earlier results and saves new ones as it goes.
+    pub fn scan_with_checkpoint(&self, checkpoint: Option<&Checkpoint>) -> Result<ScanResults, TrackerError> {
+        self.install(|| self.run_stages(checkpoint))
+    }
+
+    fn run_stages(&self, checkpoint: Option<&Checkpoint>) -> Result<ScanResults, TrackerError> {
+        let mut stage_seconds = StageSeconds::default();
+        log_info!("source-arg: {:?}", self.source_dir_path());
+
+        // -- get paths
+        let stage_instant = Instant::now();
+        check_source_dir(Path::new(self.source_dir_path()))?;
+        let paths: DiscoveredPaths = helper::find_json_files(self.source_dir_path(), &self.config);
+        let walk_interrupted: bool = shutdown::requested(); // the walk quits early on a signal
+        stage_seconds.find_json_files = stage_instant.elapsed().as_secs_f64();
+        let ocr_tracker_count: usize = paths.ocr_tracker_paths.len();
+        let ingest_tracker_count: usize = paths.ingest_tracker_paths.len();
+
+        // -- make a map of id-to-pid
+        let stage_instant = Instant::now();
+        let IngestTrackers {
+            id_to_pid_map,
+            ingest_info_map,
+        } = helper::read_ingest_trackers(&paths.ingest_tracker_paths, &self.io_throttle);
+        stage_seconds.make_id_to_pid_map = stage_instant.elapsed().as_secs_f64();
+
+        // -- ask the pid-resolver about the items with no pid
+        // (organization-files' keys are asked about too; they just aren't found)
+        let stage_instant = Instant::now();
+        let resolution: Resolution = match &self.pid_resolver {
+            Some(resolver) => {
+                let missing_keys: BTreeSet<String> = paths
+                    .ocr_tracker_paths
+                    .iter()
+                    .map(|path| helper::parse_key_from_path(path))
+                    .filter(|key| !id_to_pid_map.contains_key(key))
+                    .collect();
+                resolver.resolve(&missing_keys)
+            }
+            None => Resolution::default(),
+        };
+        stage_seconds.resolve_pids = stage_instant.elapsed().as_secs_f64();
+
+        // -- process ocr-tracker-files
+        let stage_instant = Instant::now();
+        let mut path_results: helper::PathResults = helper::process_files(
+            paths.ocr_tracker_paths,
+            &id_to_pid_map,
+            &resolution.pids,
+            &self.config.pid_url_base,
+            &self.io_throttle,
+            checkpoint,
+        );
+        for (record, path) in path_results.extracted_data_files.iter_mut().zip(&path_results.record_paths) {
+            helper::join_ingest_info(record, &helper::parse_key_from_path(path), &ingest_info_map);
+        }
+        stage_seconds.process_files = stage_instant.elapsed().as_secs_f64();
+        log_debug!("all rejected_file paths...");
+        for file in &path_results.rejected_paths {
+            log_debug!(tracker_path:% = file.display(); "rejected file");
+        }
+
+        // -- check the records' values; with `strict`, the failing ones are left out
+        let validated: ValidatedRecords = validation::apply(
+            path_results.extracted_data_files,
+            path_results.record_paths,
+            self.config.strict,
+        );
+        let validation_failures =
+            validation::count_failures(validated.records.iter().chain(&validated.invalid_records));
+
+        // -- join the review-outcomes; then keep, downrank or exclude the reviewed records
+        let reviewed: ReviewedRecords = match &self.reviews {
+            Some(reviews) => reviews.apply(
+                validated.records,
+                validated.record_paths,
+                self.config.reviewed_items,
+            ),
+            None => ReviewedRecords {
+                records: validated.records,
+                record_paths: validated.record_paths,
+                ..ReviewedRecords::default()
+            },
+        };
+        let parsed_records =
+            || reviewed.records.iter().chain(&reviewed.excluded_records).chain(&validated.invalid_records);
+
+        let status = ScanStatus::from_skipped_work(walk_interrupted, path_results.unprocessed_count);
+        if status == ScanStatus::Interrupted {
+            log_warn!(
+                "run interrupted; {} ocr-tracker-files were left unprocessed",
+                path_results.unprocessed_count
+            );
+        }
+        let summary = ScanSummary {
+            status,
+            source_dir_path: self.source_dir_path().to_string(),
+            ocr_tracker_count,
+            ingest_tracker_count,
+            error_tracker_count: paths.error_tracker_paths.len(),
+            other_count: paths.other_paths.len(),
+            walk_errors: paths.walk_errors,
+            pid_count: id_to_pid_map.len(),
+            resolved_pid_count: resolution.pids.len(),
+            resolver_failed_count: resolution.failed_count,
+            reviewed_count: reviewed.reviewed_count,
+            review_excluded_count: reviewed.excluded_records.len(),
+            review_coverage: reviewed.coverage,
+            record_count: reviewed.records.len(),
+            validation_failures,
+            validation_rejected_count: validated.invalid_records.len(),
+            schema_version_counts: schema::count_versions(parsed_records()),
+            extra_field_counts: helper::count_extra_fields(parsed_records()),
+            rejected_count: path_results.rejected_paths.len(),
+            unprocessed_count: path_results.unprocessed_count,
+            resumed_count: path_results.resumed_count,
+            thread_count: rayon::current_num_threads(),
+            stage_seconds,
+        };
+        Ok(ScanResults {
+            records: reviewed.records,
+            record_paths: reviewed.record_paths,
+            excluded_records: reviewed.excluded_records,
+            excluded_record_paths: reviewed.excluded_record_paths,
+            invalid_records: validated.invalid_records,
+            invalid_record_paths: validated.invalid_record_paths,
+            rejected_paths: path_results.rejected_paths,
+            ingest_tracker_paths: paths.ingest_tracker_paths,
+            error_tracker_paths: paths.error_tracker_paths,
+            other_paths: paths.other_paths,
+            id_to_pid_map,
+            resolved_pid_map: resolution.pids,
+            ingest_info_map,
+            summary,
+        })
+    }
+}
+
+impl ScannerBuilder {
+    /// File-name suffix of the ocr-trackers; default `ocr_complete.json`.
+    pub fn ocr_tracker_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.config.ocr_tracker_suffix = suffix.into();
+        self
+    }
+
+    /// File-name suffix of the ingest-trackers; default `ingest_complete.json`.
+    pub fn ingest_tracker_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.config.ingest_tracker_suffix = suffix.into();
+        self
+    }
+
+    /// Matched anywhere in a file-name to mark an error-tracker; default `error`.
+    pub fn error_tracker_marker(mut self, marker: impl Into<String>) -> Self {
+        self.config.error_tracker_marker = marker.into();
+        self
+    }
+
+    /// Prefix of each record's `pid_url`.
+    pub fn pid_url_base(mut self, base: impl Into<String>) -> Self {
+        self.config.pid_url_base = base.into();
+        self
+    }
+
+    /// Search-api url for looking up pids missing from the ingest-trackers; `{id}` is replaced with
+    /// the item's key. Off by default.
+    pub fn pid_resolver_url(mut self, url: impl Into<String>) -> Self {
+        self.config.pid_resolver_url = Some(url.into());
+        self
+    }
+
+    /// Json-pointer to the pid in the pid-resolver's response; default `/response/docs/0/pid`.
+    pub fn pid_resolver_pid_pointer(mut self, pointer: impl Into<String>) -> Self {
+        self.config.pid_resolver_pid_pointer = pointer.into();
+        self
+    }
+
+    /// Json file caching the pid-resolver's finds across runs; by default there's no cache.
+    pub fn pid_resolver_cache_path(mut self, path: impl Into<String>) -> Self {
+        self.config.pid_resolver_cache_path = Some(path.into());
+        self
+    }
+
+    /// Pid-resolver requests in flight at once; default 4.
+    pub fn pid_resolver_concurrency(mut self, concurrency: usize) -> Self {
+        self.config.pid_resolver_concurrency = concurrency;
+        self
+    }
+
+    /// Csv of review-outcomes (`key`, `pid`, `status`, `reviewer`, `date`) to join onto the records.
+    pub fn reviews_path(mut self, path: impl Into<String>) -> Self {
+        self.config.reviews_path = Some(path.into());
+        self
+    }
+
+    /// What happens to reviewed records; default [`ReviewedItems::Downrank`].
+    pub fn reviewed_items(mut self, reviewed_items: ReviewedItems) -> Self {
+        self.config.reviewed_items = reviewed_items;
+        self
+    }
+
+    /// Whether records that fail a validation-check are left out, rather than flagged; default false.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.config.strict = strict;
+        self
+    }
+
+    /// Ocr-tracker fields outside the Record's own to add as csv-columns, eg `["engine_version", "dpi"]`.
+    pub fn extra_columns(mut self, extra_columns: Vec<String>) -> Self {
+        self.config.extra_columns = extra_columns;
+        self
+    }
+
+    /// Size of the scanner's own thread-pool; if unset, rayon's global pool is used.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.config.threads = Some(threads);
+        self
+    }
+
+    /// Cap on tracker-files open at once, across all threads.
+    pub fn max_concurrent_reads(mut self, max_reads: usize) -> Self {
+        self.config.max_concurrent_reads = Some(max_reads);
+        self
+    }
+
+    /// Rate-limit on tracker-file reads.
+    pub fn files_per_second(mut self, rate: f64) -> Self {
+        self.config.files_per_second = Some(rate);
+        self
+    }
+
+    /// Checks the options and builds the thread-pool, if one was asked for.
+    pub fn build(self) -> Result<Scanner, TrackerError> {
+        let config: Config = self.config;
+        if config.source_dir_path.as_deref().is_none_or(str::is_empty) {
+            return Err(TrackerError::Config("`source_dir_path` must be set".to_string()));
+        }
+        if config.ocr_tracker_suffix.is_empty() || config.ingest_tracker_suffix.is_empty() {
+            return Err(TrackerError::Config(
+                "the tracker-suffixes must not be empty".to_string(),
+            ));
+        }
+        if config.threads == Some(0) {
+            return Err(TrackerError::Config("`threads` must be at least 1".to_string()));
+        }
+        if config.max_concurrent_reads == Some(0) {
+            return Err(TrackerError::Config(
+                "`max_concurrent_reads` must be at least 1".to_string(),
+            ));
+        }
+        if config.files_per_second.is_some_and(|rate| rate <= 0.0 || !rate.is_finite()) {
+            return Err(TrackerError::Config(
+                "`files_per_second` must be a positive number".to_string(),
+            ));
+        }
+        let thread_pool: Option<ThreadPool> = match config.threads {
+            Some(threads) => Some(
+                ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .map_err(|e| TrackerError::Config(format!("Failed to build the thread-pool: {}", e)))?,
+            ),
+            None => None,
+        };
+        let io_throttle = IoThrottle::new(config.max_concurrent_reads, config.files_per_second);
+        let pid_resolver: Option<PidResolver> = PidResolver::from_config(&config)?;
+        let reviews: Option<ReviewSet> = match &config.reviews_path {
+            Some(reviews_path) => Some(ReviewSet::from_csv(Path::new(reviews_path))?),
+            None => None,
+        };
+        Ok(Scanner {
+            config,
+            io_throttle,
+            thread_pool,
+            pid_resolver,
+            reviews,
+        })
+    }
+}
+
+// a missing or non-directory source-dir would otherwise just look like an empty tree
+fn check_source_dir(source_dir: &Path) -> Result<(), TrackerError> {
+    let discovery_error = |source: io::Error| TrackerError::Discovery {
+        path: source_dir.to_path_buf(),
+        source,
+    };
+    let metadata = fs::metadata(source_dir).map_err(discovery_error)?;
+    if !metadata.is_dir() {
+        return Err(discovery_error(io::Error::new(
+            io::ErrorKind::NotADirectory,
+            "not a directory",
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_skipped_work_makes_a_scan_interrupted() {
+        assert_eq!(ScanStatus::from_skipped_work(false, 0), ScanStatus::Complete); // eg, a signal after the last file
+        assert_eq!(ScanStatus::from_skipped_work(false, 3), ScanStatus::Interrupted);
+        assert_eq!(ScanStatus::from_skipped_work(true, 0), ScanStatus::Interrupted);
+        // the walk found nothing before it quit
+    }
+}
@@ -0,0 +1,228 @@
+use crate::helper::{self, Record};
+use crate::log_warn;
+use serde::de::{self, Deserializer, Error as _, Unexpected, Visitor};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, fmt, path::Path};
+
+/*  -----------------------------------------------------------------
+    The ocr-tracker schema-versions, and their normalization into a Record.
+    - v1: the original shape; every field is a number, except `script` and `image_name`.
+    - v2: since the format changed mid-project, `orientation`, `orientation_conf` and `script_conf` may be
+      `"-"` (and `script` may be `"-"`) when orientation-detection failed. Dashes become 0 in the Record.
+    A tracker's `schema_version` field (eg `2`, `"2"`, or `"v2"`), if it has one, decides its version; a tracker
+      that doesn't fit its declared version is rejected. Without the field, the version is detected by shape:
+      any dash means v2, otherwise v1 -- so a dash-free v2 tracker reads as v1, since the two only differ there.
+    Adding a version means a variant here, plus its arms in `from_declared()`, `detect()` and `normalize()`.
+    -----------------------------------------------------------------
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaVersion {
+    V1,
+    V2,
+}
+
+// an ocr-tracker as read, before it's known which version it is; a `None` is a dash-placeholder
+#[derive(Deserialize)]
+struct OcrTracker {
+    #[serde(deserialize_with = "deserialize_i32_or_dash")]
+    orientation: Option<i32>,
+    #[serde(deserialize_with = "deserialize_f64_or_dash")]
+    orientation_conf: Option<f64>,
+    script: String,
+    #[serde(deserialize_with = "deserialize_f64_or_dash")]
+    script_conf: Option<f64>,
+    image_name: String,
+    word_count: i32,
+    avg_confidence: f64,
+    below_90: f64,
+    below_60: f64,
+    below_30: f64,
+    #[serde(default)]
+    schema_version: Option<Value>,
+    #[serde(flatten)]
+    extra: BTreeMap<String, Value>,
+}
+
+impl SchemaVersion {
+    /// The version a `schema_version` field names, if it's one this crate knows.
+    pub fn from_declared(declared: &Value) -> Option<SchemaVersion> {
+        let text: String = match declared {
+            Value::Number(number) => number.to_string(),
+            Value::String(text) => text.trim().trim_start_matches(['v', 'V']).to_string(),
+            _ => return None,
+        };
+        match text.as_str() {
+            "1" => Some(SchemaVersion::V1),
+            "2" => Some(SchemaVersion::V2),
+            _ => None,
+        }
+    }
+
+    // the version by shape, for trackers without a `schema_version` field
+    fn detect(tracker: &OcrTracker) -> SchemaVersion {
+        if has_dashes(tracker) {
+            SchemaVersion::V2
+        } else {
+            SchemaVersion::V1
+        }
+    }
+
+    // whether the tracker's shape is allowed in this version
+    fn fits(self, tracker: &OcrTracker) -> bool {
+        match self {
+            SchemaVersion::V1 => !has_dashes(tracker),
+            SchemaVersion::V2 => true,
+        }
+    }
+}
+
+/*  -----------------------------------------------------------------
+    Parses an ocr-tracker's bytes into a normalized Record, with its `schema_version` set.
+    Errors if the bytes aren't an ocr-tracker of any version (eg an organization-file), if the declared
+    version is unknown, or if the tracker doesn't fit its declared version. The last two are also logged as
+    warnings, since they're likely real trackers from a newer (or misconfigured) ocr-pipeline.
+    -----------------------------------------------------------------
+*/
+pub fn parse_ocr_tracker(bytes: &[u8], tracker_path: &Path) -> Result<Record, serde_json::Error> {
+    let tracker: OcrTracker = serde_json::from_slice(bytes)?;
+    let version: SchemaVersion = match &tracker.schema_version {
+        Some(declared) => {
+            let checked: Result<SchemaVersion, String> = match SchemaVersion::from_declared(declared) {
+                None => Err(format!("unsupported schema_version {}", declared)),
+                Some(version) if !version.fits(&tracker) => {
+                    Err(format!("doesn't fit its declared schema_version {}", declared))
+                }
+                Some(version) => Ok(version),
+            };
+            match checked {
+                Ok(version) => version,
+                Err(problem) => {
+                    log_warn!(tracker_path:% = tracker_path.display(); "rejecting ocr-tracker -- {}", problem);
+                    return Err(serde_json::Error::custom(problem));
+                }
+            }
+        }
+        None => SchemaVersion::detect(&tracker),
+    };
+    Ok(normalize(tracker, version))
+}
+
+// maps a tracker of a known version onto the Record's fields
+fn normalize(tracker: OcrTracker, version: SchemaVersion) -> Record {
+    match version {
+        SchemaVersion::V1 | SchemaVersion::V2 => Record {
+            orientation: tracker.orientation.unwrap_or(0),
+            orientation_conf: tracker.orientation_conf.unwrap_or(0.0),
+            script: tracker.script,
+            script_conf: tracker.script_conf.unwrap_or(0.0),
+            image_name: tracker.image_name,
+            word_count: tracker.word_count,
+            avg_confidence: tracker.avg_confidence,
+            below_90: tracker.below_90,
+            below_60: tracker.below_60,
+            below_30: tracker.below_30,
+            schema_version: Some(version),
+            extra: rename_clashing_fields(tracker.extra),
+            ..Record::default()
+        },
+    }
+}
+
+/*  -----------------------------------------------------------------
+    Counts the records per schema-version, for the summary-json.
+    -----------------------------------------------------------------
+*/
+pub fn count_versions<'a>(records: impl IntoIterator<Item = &'a Record>) -> BTreeMap<SchemaVersion, usize> {
+    let mut counts: BTreeMap<SchemaVersion, usize> = BTreeMap::new();
+    for version in records.into_iter().filter_map(|record| record.schema_version) {
+        *counts.entry(version).or_default() += 1;
+    }
+    counts
+}
+
+/*  -----------------------------------------------------------------
+    Extra-fields named like one of the Record's own columns (eg a `reviewer` or `pid` the ocr-pipeline
+    started writing) would clash with that column; they're kept as `ocr_(name)` instead.
+    -----------------------------------------------------------------
+*/
+fn rename_clashing_fields(extra: BTreeMap<String, Value>) -> BTreeMap<String, Value> {
+    if !extra.keys().any(|key| helper::is_fixed_column(key)) {
+        return extra; // the usual case
+    }
+    let (clashing, mut renamed): (BTreeMap<String, Value>, BTreeMap<String, Value>) =
+        extra.into_iter().partition(|(key, _)| helper::is_fixed_column(key));
+    for (mut key, value) in clashing {
+        while helper::is_fixed_column(&key) || renamed.contains_key(&key) {
+            key = format!("ocr_{}", key); // eg a tracker with both `pid` and `ocr_pid` keeps both
+        }
+        renamed.insert(key, value);
+    }
+    renamed
+}
+
+fn has_dashes(tracker: &OcrTracker) -> bool {
+    tracker.orientation.is_none()
+        || tracker.orientation_conf.is_none()
+        || tracker.script_conf.is_none()
+        || tracker.script == "-"
+}
+
+// needed to handle the fact that what used to be integers are now sometimes dashes
+// (visitors, rather than going through a `Value`, so the dash-check doesn't allocate)
+fn deserialize_i32_or_dash<'de, D>(deserializer: D) -> Result<Option<i32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct I32OrDash;
+    impl Visitor<'_> for I32OrDash {
+        type Value = Option<i32>;
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("an integer or '-'")
+        }
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<Option<i32>, E> {
+            Ok(Some(v as i32))
+        }
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<Option<i32>, E> {
+            Ok(Some(v as i32))
+        }
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Option<i32>, E> {
+            match v {
+                "-" => Ok(None),
+                _ => Err(E::invalid_value(Unexpected::Str(v), &self)),
+            }
+        }
+    }
+    deserializer.deserialize_any(I32OrDash)
+}
+
+// needed to handle the fact that what used to be floats are now sometimes dashes
+fn deserialize_f64_or_dash<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct F64OrDash;
+    impl Visitor<'_> for F64OrDash {
+        type Value = Option<f64>;
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a float or '-'")
+        }
+        fn visit_f64<E: de::Error>(self, v: f64) -> Result<Option<f64>, E> {
+            Ok(Some(v))
+        }
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<Option<f64>, E> {
+            Ok(Some(v as f64))
+        }
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<Option<f64>, E> {
+            Ok(Some(v as f64))
+        }
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Option<f64>, E> {
+            match v {
+                "-" => Ok(None),
+                _ => Err(E::invalid_value(Unexpected::Str(v), &self)),
+            }
+        }
+    }
+    deserializer.deserialize_any(F64OrDash)
+}
@@ -0,0 +1,451 @@
+use crate::error::TrackerError;
+use crate::helper::{self, Record};
+use crate::shutdown;
+use crate::{log_debug, log_info, log_warn};
+use serde_json::{json, Map, Value};
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fs::File,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/*  -----------------------------------------------------------------
+    Serve-mode: holds a scan's records in memory and answers read-only http requests about them,
+    so curators' quick questions don't need a rerun.
+
+        GET /                          the endpoints
+        GET /summary                   the run's returned json
+        GET /items                     records, as `{"total", "offset", "limit", "items"}`
+            ?where=avg_confidence<60     filters; repeatable, and all must match; ops are = != < <= > >= and ~ (contains)
+            &sort=-below_60,image_name   sort-columns; `-` for descending
+            &offset=0&limit=100          paging; `limit` is at most 10000
+        GET /items.csv                 the same records (with `where` and `sort`, but no paging), as a csv download
+        GET /items/{key}               one record, by item-key (as the scanner parses it from the tracker's file-name)
+        GET /collections/{prefix}      rollup-stats for the records whose key starts with `prefix`
+
+    ResultsServer does the listening; ServedScan holds the records and answers the requests.
+    Requests are answered one at a time, on the calling thread, until a Ctrl-C / SIGTERM.
+    -----------------------------------------------------------------
+*/
+pub struct ResultsServer {
+    server: Server,
+}
+
+/// What serve-mode serves: the records, each as its csv-row, plus the summary-json.
+pub struct ServedScan {
+    records: Vec<Record>,
+    extra_columns: Vec<String>,          // the promoted extra-fields, as in the csv
+    rows: Vec<Map<String, Value>>,       // each record as a json-object, for filtering and sorting
+    keys: Vec<String>,                   // each record's item-key, in the same order
+    key_indexes: HashMap<String, usize>, // item-key to its record's index, for `/items/{key}`
+    summary: Value,
+}
+
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 10_000;
+const POLL_INTERVAL: Duration = Duration::from_millis(250); // how soon a Ctrl-C is noticed
+
+impl ServedScan {
+    /// `record_paths` are the records' ocr-trackers, in the same order, as in ScanResults; the item-keys come from them.
+    pub fn new(
+        records: Vec<Record>, record_paths: &[PathBuf], extra_columns: Vec<String>, summary: Value,
+    ) -> ServedScan {
+        let keys: Vec<String> = record_paths.iter().map(|path| helper::parse_key_from_path(path)).collect();
+        ServedScan::with_keys(records, keys, extra_columns, summary)
+    }
+
+    fn with_keys(
+        records: Vec<Record>, keys: Vec<String>, extra_columns: Vec<String>, summary: Value,
+    ) -> ServedScan {
+        let rows: Vec<Map<String, Value>> =
+            records.iter().map(|record| helper::csv_row(record, &extra_columns)).collect();
+        let mut key_indexes: HashMap<String, usize> = HashMap::with_capacity(keys.len());
+        for (i, key) in keys.iter().enumerate() {
+            key_indexes.entry(key.clone()).or_insert(i); // the first, if a key repeats
+        }
+        ServedScan {
+            records,
+            extra_columns,
+            rows,
+            keys,
+            key_indexes,
+            summary,
+        }
+    }
+
+    /*  -----------------------------------------------------------------
+        Loads the records from an earlier run's csv, instead of scanning.
+        The summary is just the csv's path and record-count, since the rest of that run's json isn't in the csv.
+        The csv has no tracker-paths, so the item-keys are parsed from the image-names, by the same rule.
+        Columns beyond the Record's own are that run's promoted extra-fields, and are served as such.
+        -----------------------------------------------------------------
+    */
+    pub fn from_csv(csv_path: &Path) -> Result<ServedScan, TrackerError> {
+        let read_error = |e: csv::Error| TrackerError::Read {
+            path: csv_path.to_path_buf(),
+            source: e.into(),
+        };
+        let file = File::open(csv_path).map_err(|e| TrackerError::Read {
+            path: csv_path.to_path_buf(),
+            source: e,
+        })?;
+        let mut reader = csv::Reader::from_reader(file);
+        let fixed_columns: Vec<String> = helper::csv_columns(&[]);
+        let extra_columns: Vec<String> = reader
+            .headers()
+            .map_err(read_error)?
+            .iter()
+            .filter(|header| !fixed_columns.iter().any(|column| column == header))
+            .map(String::from)
+            .collect();
+        let mut records: Vec<Record> = Vec::new();
+        for result in reader.deserialize::<Record>() {
+            let mut record: Record = result.map_err(read_error)?;
+            record.extra.retain(|_, value| value != ""); // a blank cell is a missing field, as when it was written
+            records.push(record);
+        }
+        let summary = json!({
+            "source_csv_path": csv_path.to_string_lossy(),
+            "ocr_data_vector_count": records.len(),
+        });
+        let keys: Vec<String> =
+            records.iter().map(|record| helper::parse_key_from_path(Path::new(&record.image_name))).collect();
+        Ok(ServedScan::with_keys(records, keys, extra_columns, summary))
+    }
+
+    // returns the status, content-type and body for a GET of `url`
+    fn route(&self, url: &str) -> (u16, &'static str, Vec<u8>) {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let params: Vec<(String, String)> = parse_query(query);
+        let path: String = percent_decode(path);
+        let result: Result<Value, (u16, String)> = match path.trim_end_matches('/') {
+            "" => Ok(json!({ "endpoints": [
+                "/summary", "/items", "/items.csv", "/items/{key}", "/collections/{prefix}"
+            ] })),
+            "/summary" => Ok(self.summary.clone()),
+            "/items" => self.items(&params),
+            "/items.csv" => {
+                return match self.items_csv(&params) {
+                    Ok(csv_bytes) => (200, "text/csv; charset=utf-8", csv_bytes),
+                    Err((status, message)) => error_reply(status, &message),
+                };
+            }
+            other => match (other.strip_prefix("/items/"), other.strip_prefix("/collections/")) {
+                (Some(key), _) => self.item(key),
+                (_, Some(prefix)) => self.collection(prefix),
+                _ => Err((404, format!("no endpoint at ``{}``; see `/`", path))),
+            },
+        };
+        match result {
+            Ok(value) => (200, "application/json", json_bytes(&value)),
+            Err((status, message)) => error_reply(status, &message),
+        }
+    }
+
+    // the indexes of the records matching the `where`s, in `sort` order
+    fn select(&self, params: &[(String, String)]) -> Result<Vec<usize>, (u16, String)> {
+        let columns: &Map<String, Value> = match self.rows.first() {
+            Some(row) => row,
+            None => return Ok(Vec::new()),
+        };
+        let mut conditions: Vec<Condition> = Vec::new();
+        let mut sort_keys: Vec<(String, bool)> = Vec::new();
+        for (name, value) in params {
+            match name.as_str() {
+                "where" => conditions.push(Condition::parse(value, columns)?),
+                "sort" => {
+                    for column in value.split(',').filter(|c| !c.is_empty()) {
+                        let (column, descending) = match column.strip_prefix('-') {
+                            Some(column) => (column, true),
+                            None => (column, false),
+                        };
+                        if !columns.contains_key(column) {
+                            return Err((400, format!("can't sort on unknown column ``{}``", column)));
+                        }
+                        sort_keys.push((column.to_string(), descending));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let rows = &self.rows;
+        let mut selected: Vec<usize> = (0..rows.len())
+            .filter(|&i| conditions.iter().all(|condition| condition.matches(&rows[i])))
+            .collect();
+        selected.sort_by(|&a, &b| {
+            sort_keys
+                .iter()
+                .map(|(column, descending)| {
+                    let ordering = compare_values(&rows[a][column], &rows[b][column]);
+                    if *descending {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    }
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+        Ok(selected)
+    }
+
+    fn items(&self, params: &[(String, String)]) -> Result<Value, (u16, String)> {
+        let offset: usize = number_param(params, "offset")?.unwrap_or(0);
+        let limit: usize = number_param(params, "limit")?.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+        let selected: Vec<usize> = self.select(params)?;
+        let items: Vec<&Map<String, Value>> =
+            selected.iter().skip(offset).take(limit).map(|&i| &self.rows[i]).collect();
+        Ok(json!({
+            "total": selected.len(),
+            "offset": offset,
+            "limit": limit,
+            "items": items,
+        }))
+    }
+
+    fn items_csv(&self, params: &[(String, String)]) -> Result<Vec<u8>, (u16, String)> {
+        let selected: Vec<usize> = self.select(params)?;
+        let records: Vec<&Record> = selected.iter().map(|&i| &self.records[i]).collect();
+        let mut csv_bytes: Vec<u8> = Vec::new();
+        helper::write_records_csv(records, &self.extra_columns, &mut csv_bytes)
+            .map_err(|e| (500, format!("Failed to write the csv: {}", e)))?;
+        Ok(csv_bytes)
+    }
+
+    fn item(&self, key: &str) -> Result<Value, (u16, String)> {
+        match self.key_indexes.get(key) {
+            Some(&i) => Ok(Value::Object(self.rows[i].clone())),
+            None => Err((404, format!("no item with key ``{}``", key))),
+        }
+    }
+
+    /*  -----------------------------------------------------------------
+        Rollup-stats for the records whose key starts with `prefix` (eg a collection, `HH001545`).
+        The confidence-stats leave out blank pages (no words), whose zeros would drag the means down.
+        -----------------------------------------------------------------
+    */
+    fn collection(&self, prefix: &str) -> Result<Value, (u16, String)> {
+        let records: Vec<&Record> = self
+            .keys
+            .iter()
+            .zip(&self.records)
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(_, record)| record)
+            .collect();
+        if records.is_empty() {
+            return Err((404, format!("no items with keys starting ``{}``", prefix)));
+        }
+        let with_words: Vec<&&Record> = records.iter().filter(|record| record.word_count > 0).collect();
+        let mean = |value: fn(&Record) -> f64| -> Option<f64> {
+            if with_words.is_empty() {
+                return None;
+            }
+            let sum: f64 = with_words.iter().map(|record| value(record)).sum();
+            Some(round2(sum / with_words.len() as f64))
+        };
+        let confidences = with_words.iter().map(|record| record.avg_confidence);
+        Ok(json!({
+            "prefix": prefix,
+            "record_count": records.len(),
+            "with_pid_count": records.iter().filter(|record| record.pid.is_some()).count(),
+            "blank_page_count": records.len() - with_words.len(),
+            "word_count_total": records.iter().map(|record| record.word_count as i64).sum::<i64>(),
+            "avg_confidence": {
+                "mean": mean(|record| record.avg_confidence),
+                "min": confidences.clone().reduce(f64::min),
+                "max": confidences.reduce(f64::max),
+            },
+            "below_90_mean": mean(|record| record.below_90),
+            "below_60_mean": mean(|record| record.below_60),
+            "below_30_mean": mean(|record| record.below_30),
+        }))
+    }
+}
+
+impl ResultsServer {
+    /// Starts listening on `address`, eg `127.0.0.1:8080`; port 0 picks a free port.
+    pub fn bind(address: &str) -> Result<ResultsServer, TrackerError> {
+        let server = Server::http(address)
+            .map_err(|e| TrackerError::Config(format!("Failed to listen on ``{}``: {}", address, e)))?;
+        Ok(ResultsServer { server })
+    }
+
+    /// The address actually listened on.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+
+    /// Answers requests about `scan` until a Ctrl-C / SIGTERM.
+    pub fn run(&self, scan: &ServedScan) {
+        log_info!(
+            "serving {} records at {:?}",
+            scan.records.len(),
+            self.local_addr()
+        );
+        while !shutdown::requested() {
+            match self.server.recv_timeout(POLL_INTERVAL) {
+                Ok(Some(request)) => answer(scan, request),
+                Ok(None) => {}
+                Err(e) => log_warn!(error:% = e; "failed to receive a request"),
+            }
+        }
+        log_info!("stopped serving");
+    }
+}
+
+// answers one request, logging (but otherwise ignoring) a failure to send the response
+fn answer(scan: &ServedScan, request: Request) {
+    let (status, content_type, body) = if *request.method() == Method::Get {
+        scan.route(request.url())
+    } else {
+        error_reply(405, "only GET is supported")
+    };
+    log_debug!(method:% = request.method(), url = request.url(), status = status; "request answered");
+    let mut response = Response::from_data(body).with_status_code(status);
+    for (name, value) in [
+        ("Content-Type", content_type),
+        ("Access-Control-Allow-Origin", "*"),
+    ] {
+        if let Ok(header) = Header::from_bytes(name, value) {
+            response.add_header(header);
+        }
+    }
+    if content_type.starts_with("text/csv") {
+        if let Ok(header) = Header::from_bytes("Content-Disposition", "attachment; filename=\"items.csv\"") {
+            response.add_header(header);
+        }
+    }
+    if let Err(e) = request.respond(response) {
+        log_warn!(error:% = e; "failed to send a response");
+    }
+}
+
+// one `where` filter, eg `avg_confidence<60`
+struct Condition {
+    column: String,
+    operator: &'static str,
+    value: String,
+}
+
+impl Condition {
+    fn parse(text: &str, columns: &Map<String, Value>) -> Result<Condition, (u16, String)> {
+        // the two-character operators first, so `<=` isn't read as `<`
+        const OPERATORS: [&str; 7] = ["!=", "<=", ">=", "=", "<", ">", "~"];
+        let found = text
+            .char_indices()
+            .find_map(|(i, _)| OPERATORS.iter().find(|op| text[i..].starts_with(**op)).map(|op| (i, *op)));
+        let Some((index, operator)) = found else {
+            return Err((400, format!("``{}`` has no operator (= != < <= > >= ~)", text)));
+        };
+        let column: &str = text[..index].trim();
+        if !columns.contains_key(column) {
+            return Err((400, format!("can't filter on unknown column ``{}``", column)));
+        }
+        Ok(Condition {
+            column: column.to_string(),
+            operator,
+            value: text[index + operator.len()..].trim().to_string(),
+        })
+    }
+
+    fn matches(&self, row: &Map<String, Value>) -> bool {
+        let cell: &Value = row.get(&self.column).unwrap_or(&Value::Null);
+        if self.operator == "~" {
+            return cell_text(cell).contains(&self.value);
+        }
+        let ordering: Ordering = compare_values(cell, &Value::String(self.value.clone()));
+        match self.operator {
+            "=" => ordering.is_eq(),
+            "!=" => ordering.is_ne(),
+            "<" => ordering.is_lt(),
+            "<=" => ordering.is_le(),
+            ">" => ordering.is_gt(),
+            _ => ordering.is_ge(),
+        }
+    }
+}
+
+// numbers (and numeric strings) compare as numbers; everything else as text; a missing value sorts first
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    let (a_text, b_text) = (cell_text(a), cell_text(b));
+    match (a_text.parse::<f64>(), b_text.parse::<f64>()) {
+        (Ok(a_number), Ok(b_number)) => a_number.partial_cmp(&b_number).unwrap_or(Ordering::Equal),
+        _ => a_text.cmp(&b_text),
+    }
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn number_param(params: &[(String, String)], name: &str) -> Result<Option<usize>, (u16, String)> {
+    match params.iter().rev().find(|(key, _)| key == name) {
+        Some((_, value)) => value.parse::<usize>().map(Some).map_err(|_| {
+            (
+                400,
+                format!("`{}` must be a whole number, not ``{}``", name, value),
+            )
+        }),
+        None => Ok(None),
+    }
+}
+
+fn error_reply(status: u16, message: &str) -> (u16, &'static str, Vec<u8>) {
+    (
+        status,
+        "application/json",
+        json_bytes(&json!({ "error": message })),
+    )
+}
+
+fn json_bytes(value: &Value) -> Vec<u8> {
+    serde_json::to_vec_pretty(value).unwrap_or_default()
+}
+
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(name), percent_decode(value))
+        })
+        .collect()
+}
+
+// `%3C60` -> `<60`, and `+` -> space
+fn percent_decode(text: &str) -> String {
+    let bytes: &[u8] = text.as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i: usize = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                match text.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
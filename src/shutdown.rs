@@ -0,0 +1,26 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/*  -----------------------------------------------------------------
+    Ctrl-C / SIGTERM handling.
+    - The first signal sets a flag; process_files() checks it before each file and stops dispatching new ones,
+      so main() can still save what was parsed to a partial csv and print the json.
+    - A second signal exits immediately, for when even that's taking too long.
+    -----------------------------------------------------------------
+*/
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+pub const INTERRUPTED_EXIT_CODE: i32 = 130; // the shell-convention for "terminated by Ctrl-C"
+
+pub fn install_handler() -> Result<(), ctrlc::Error> {
+    ctrlc::set_handler(|| {
+        if SHUTDOWN_REQUESTED.swap(true, Ordering::SeqCst) {
+            eprintln!("second interrupt received; exiting without saving");
+            std::process::exit(INTERRUPTED_EXIT_CODE);
+        }
+        eprintln!("interrupt received; finishing in-flight files, then saving partial output (interrupt again to quit now)");
+    })
+}
+
+pub fn requested() -> bool {
+    SHUTDOWN_REQUESTED.load(Ordering::SeqCst)
+}
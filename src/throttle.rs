@@ -0,0 +1,80 @@
+use std::{
+    sync::{Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+/*  -----------------------------------------------------------------
+    Limits how hard we hit the source-storage when reading tracker files.
+    - `max_concurrent_reads` caps the number of files open at once, independent of the rayon thread-count.
+    - `files_per_second` spaces out the start of each read.
+    Both are optional; with neither set, acquire() returns immediately.
+    Used by make_id_to_pid_map() and process_files() around each file open-and-read.
+    -----------------------------------------------------------------
+*/
+pub struct IoThrottle {
+    max_concurrent_reads: Option<usize>,
+    open_reads: Mutex<usize>,
+    read_finished: Condvar,
+    interval: Option<Duration>,
+    next_read_at: Mutex<Instant>,
+}
+
+/*  -----------------------------------------------------------------
+    Returned by IoThrottle::acquire(); the read-slot is released when this is dropped.
+    -----------------------------------------------------------------
+*/
+pub struct ReadPermit<'a> {
+    throttle: &'a IoThrottle,
+}
+
+impl IoThrottle {
+    pub fn new(max_concurrent_reads: Option<usize>, files_per_second: Option<f64>) -> IoThrottle {
+        IoThrottle {
+            max_concurrent_reads: max_concurrent_reads.filter(|n| *n > 0),
+            open_reads: Mutex::new(0),
+            read_finished: Condvar::new(),
+            interval: files_per_second.filter(|r| *r > 0.0).map(|r| Duration::from_secs_f64(1.0 / r)),
+            next_read_at: Mutex::new(Instant::now()),
+        }
+    }
+
+    /*  -----------------------------------------------------------------
+        Blocks until a read is allowed by both the rate-limit and the concurrency-cap.
+        -----------------------------------------------------------------
+    */
+    pub fn acquire(&self) -> ReadPermit<'_> {
+        // -- rate-limit: reserve the next time-slot, then sleep until it arrives
+        if let Some(interval) = self.interval {
+            let slot: Instant = {
+                let mut next_read_at = self.next_read_at.lock().unwrap();
+                let slot: Instant = (*next_read_at).max(Instant::now());
+                *next_read_at = slot + interval;
+                slot
+            };
+            let now = Instant::now();
+            if slot > now {
+                thread::sleep(slot - now);
+            }
+        }
+        // -- concurrency-cap: wait for an open read-slot
+        if let Some(max_reads) = self.max_concurrent_reads {
+            let mut open_reads = self.open_reads.lock().unwrap();
+            while *open_reads >= max_reads {
+                open_reads = self.read_finished.wait(open_reads).unwrap();
+            }
+            *open_reads += 1;
+        }
+        ReadPermit { throttle: self }
+    }
+}
+
+impl Drop for ReadPermit<'_> {
+    fn drop(&mut self) {
+        if self.throttle.max_concurrent_reads.is_some() {
+            let mut open_reads = self.throttle.open_reads.lock().unwrap();
+            *open_reads -= 1;
+            self.throttle.read_finished.notify_one();
+        }
+    }
+}
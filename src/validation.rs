@@ -0,0 +1,132 @@
+use crate::helper::Record;
+use serde::de::IntoDeserializer;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{collections::BTreeMap, path::PathBuf};
+
+/*  -----------------------------------------------------------------
+    Range- and consistency-checks on each record's ocr-statistics, run after parsing and before the reviews.
+    - A record that fails a check gets its warning-codes in the `validation_warnings` column, joined by `;`.
+    - The checks: `avg_confidence`, `script_conf` and the `below_*` percentages are within 0-100;
+      `word_count` isn't negative; `below_30 <= below_60 <= below_90`, since each includes the ones below it;
+      and `orientation` is 0, 90, 180 or 270.
+    - Dash-placeholders were normalized to 0 by schema.rs, so they pass.
+    - With `strict`, failing records are left out of the csv, rather than passed through with their warnings.
+    -----------------------------------------------------------------
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationWarning {
+    AvgConfidenceOutOfRange,
+    NegativeWordCount,
+    BelowOutOfRange,
+    BelowNotMonotonic,
+    OrientationNotRightAngle,
+    ScriptConfOutOfRange,
+}
+
+/// The records after validation, split into the ones that stay in the csv and the ones `strict` rejected.
+#[derive(Clone, Debug, Default)]
+pub struct ValidatedRecords {
+    pub records: Vec<Record>,
+    pub record_paths: Vec<PathBuf>,
+    pub invalid_records: Vec<Record>, // only with `strict`
+    pub invalid_record_paths: Vec<PathBuf>,
+}
+
+impl ValidationWarning {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ValidationWarning::AvgConfidenceOutOfRange => "avg_confidence_out_of_range",
+            ValidationWarning::NegativeWordCount => "negative_word_count",
+            ValidationWarning::BelowOutOfRange => "below_out_of_range",
+            ValidationWarning::BelowNotMonotonic => "below_not_monotonic",
+            ValidationWarning::OrientationNotRightAngle => "orientation_not_right_angle",
+            ValidationWarning::ScriptConfOutOfRange => "script_conf_out_of_range",
+        }
+    }
+}
+
+/// The warning-codes for a record's values; empty if they're all plausible.
+pub fn validate(record: &Record) -> Vec<ValidationWarning> {
+    let is_percent = |value: f64| (0.0..=100.0).contains(&value); // also false for NaN
+    let mut warnings: Vec<ValidationWarning> = Vec::new();
+    if !is_percent(record.avg_confidence) {
+        warnings.push(ValidationWarning::AvgConfidenceOutOfRange);
+    }
+    if record.word_count < 0 {
+        warnings.push(ValidationWarning::NegativeWordCount);
+    }
+    if ![record.below_30, record.below_60, record.below_90].into_iter().all(is_percent) {
+        warnings.push(ValidationWarning::BelowOutOfRange);
+    }
+    if record.below_30 > record.below_60 || record.below_60 > record.below_90 {
+        warnings.push(ValidationWarning::BelowNotMonotonic);
+    }
+    if ![0, 90, 180, 270].contains(&record.orientation) {
+        warnings.push(ValidationWarning::OrientationNotRightAngle);
+    }
+    if !is_percent(record.script_conf) {
+        warnings.push(ValidationWarning::ScriptConfOutOfRange);
+    }
+    warnings
+}
+
+/*  -----------------------------------------------------------------
+    Sets each record's `validation_warnings`; then, with `strict`, moves the failing records out.
+    Both groups keep their path-order.
+    -----------------------------------------------------------------
+*/
+pub fn apply(records: Vec<Record>, record_paths: Vec<PathBuf>, strict: bool) -> ValidatedRecords {
+    let mut validated = ValidatedRecords::default();
+    for (mut record, path) in records.into_iter().zip(record_paths) {
+        record.validation_warnings = validate(&record);
+        if strict && !record.validation_warnings.is_empty() {
+            validated.invalid_record_paths.push(path);
+            validated.invalid_records.push(record);
+        } else {
+            validated.record_paths.push(path);
+            validated.records.push(record);
+        }
+    }
+    validated
+}
+
+/*  -----------------------------------------------------------------
+    Counts the records per warning-code, for the summary-json's `validation_failures`.
+    -----------------------------------------------------------------
+*/
+pub fn count_failures<'a>(
+    records: impl IntoIterator<Item = &'a Record>,
+) -> BTreeMap<ValidationWarning, usize> {
+    let mut counts: BTreeMap<ValidationWarning, usize> = BTreeMap::new();
+    for warning in records.into_iter().flat_map(|record| &record.validation_warnings) {
+        *counts.entry(*warning).or_default() += 1;
+    }
+    counts
+}
+
+/// The `validation_warnings` csv-cell: the codes joined by `;`, or empty.
+pub mod warning_codes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        warnings: &[ValidationWarning], serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        if warnings.is_empty() {
+            return serializer.serialize_none();
+        }
+        let codes: Vec<&str> = warnings.iter().map(|warning| warning.as_str()).collect();
+        serializer.serialize_str(&codes.join(";"))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<ValidationWarning>, D::Error> {
+        let cell: Option<String> = Option::deserialize(deserializer)?;
+        cell.unwrap_or_default()
+            .split(';')
+            .filter(|code| !code.is_empty())
+            .map(|code| ValidationWarning::deserialize(code.into_deserializer()))
+            .collect()
+    }
+}
@@ -0,0 +1,356 @@
+use crate::config::Config;
+use crate::error::TrackerError;
+use crate::helper::{self, DiscoveredPaths, IngestInfo, IngestTrackers, Record};
+use crate::reviews::{self, ReviewedItems};
+use crate::scanner::{ScanResults, ScanSummary, Scanner};
+use crate::schema;
+use crate::validation;
+use crate::{log_debug, log_info, log_warn};
+use notify::event::{AccessKind, AccessMode};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use rayon::prelude::*;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+/*  -----------------------------------------------------------------
+    Watch-mode: after the initial scan, keeps the results current as trackers land.
+    - watch_source_dir() turns filesystem-notifications (inotify, on Linux) into batches of changed paths,
+      one batch per debounce-interval.
+    - LiveResults holds a scan's results keyed by path, and applies each batch: new or modified trackers
+      are re-parsed (ocr-trackers with the usual process_file(); ingest-trackers with read_ingest_trackers()),
+      deleted ones are dropped, and when an ingest-tracker changes, its records' pid and ingest-fields are re-joined.
+    - Keys the pid-resolver found during the initial scan still count; new items aren't sent to it.
+    - Re-parsed records are validated, and joined to the reviews-csv loaded at the start; as in the initial
+      scan, invalid records are left out with `strict`, and reviewed records are kept, downranked or excluded.
+    -----------------------------------------------------------------
+*/
+pub struct LiveResults {
+    records: BTreeMap<PathBuf, Record>, // including reviewed or invalid records excluded from the output
+    rejected_paths: BTreeSet<PathBuf>,
+    ingest_tracker_paths: BTreeSet<PathBuf>,
+    error_tracker_paths: BTreeSet<PathBuf>,
+    other_paths: BTreeSet<PathBuf>,
+    id_to_pid_map: BTreeMap<String, String>,
+    resolved_pid_map: BTreeMap<String, String>,
+    ingest_info_map: BTreeMap<String, IngestInfo>,
+    reviewed_items: ReviewedItems,
+    strict: bool,
+    summary: ScanSummary,
+}
+
+/// What one batch of changes did.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BatchCounts {
+    pub parsed_count: usize,   // ocr-trackers (re)parsed into records
+    pub rejected_count: usize, // ocr-trackers that didn't parse
+    pub removed_count: usize,  // tracked paths that are gone
+    pub pid_changes: usize,    // id-to-pid entries added, changed or removed
+}
+
+impl LiveResults {
+    /// Takes over a complete scan's results; `config` should be the scan's own.
+    pub fn from_scan(results: ScanResults, config: &Config) -> LiveResults {
+        let record_paths = results
+            .record_paths
+            .into_iter()
+            .chain(results.excluded_record_paths)
+            .chain(results.invalid_record_paths);
+        let records =
+            results.records.into_iter().chain(results.excluded_records).chain(results.invalid_records);
+        LiveResults {
+            records: record_paths.zip(records).collect(),
+            rejected_paths: results.rejected_paths.into_iter().collect(),
+            ingest_tracker_paths: results.ingest_tracker_paths.into_iter().collect(),
+            error_tracker_paths: results.error_tracker_paths.into_iter().collect(),
+            other_paths: results.other_paths.into_iter().collect(),
+            id_to_pid_map: results.id_to_pid_map,
+            resolved_pid_map: results.resolved_pid_map,
+            ingest_info_map: results.ingest_info_map,
+            reviewed_items: config.reviewed_items,
+            strict: config.strict,
+            summary: results.summary,
+        }
+    }
+
+    /// The current records, in ocr-tracker path-order; reviewed ones are moved to the end, or left out,
+    /// per `reviewed_items`; invalid ones are left out with `strict`.
+    pub fn records(&self) -> impl Iterator<Item = &Record> {
+        let reviewed_items = self.reviewed_items;
+        let in_place = self
+            .valid_records()
+            .filter(move |record| record.review_status.is_none() || reviewed_items == ReviewedItems::Keep);
+        let downranked = self.valid_records().filter(move |record| {
+            record.review_status.is_some() && reviewed_items == ReviewedItems::Downrank
+        });
+        in_place.chain(downranked)
+    }
+
+    // the records `strict` doesn't leave out, with their paths
+    fn valid_entries(&self) -> impl Iterator<Item = (&PathBuf, &Record)> {
+        let strict = self.strict;
+        self.records.iter().filter(move |(_, record)| !strict || record.validation_warnings.is_empty())
+    }
+
+    fn valid_records(&self) -> impl Iterator<Item = &Record> {
+        self.valid_entries().map(|(_, record)| record)
+    }
+
+    pub fn error_tracker_paths(&self) -> Vec<PathBuf> {
+        self.error_tracker_paths.iter().cloned().collect()
+    }
+
+    /// The initial scan's summary, with the counts brought up to date.
+    pub fn summary(&self) -> &ScanSummary {
+        &self.summary
+    }
+
+    /*  -----------------------------------------------------------------
+        Applies a batch of changed paths: each is re-classified if it's there, and dropped if it isn't.
+        A changed directory (eg a new item-dir, whose files may have landed before it was watched)
+        is walked, and everything under it is applied too.
+        -----------------------------------------------------------------
+    */
+    pub fn apply(&mut self, changed_paths: &BTreeSet<PathBuf>, scanner: &Scanner) -> BatchCounts {
+        let config: &Config = scanner.config();
+        let mut counts = BatchCounts::default();
+        let mut changed_keys: BTreeMap<String, Option<String>> = BTreeMap::new(); // each with its pid before the batch
+        let mut present_files: BTreeSet<PathBuf> = BTreeSet::new(); // a new dir's files may be in the batch too
+        for path in changed_paths {
+            if path.is_dir() {
+                let found: DiscoveredPaths = scanner.install(|| helper::find_json_files(path, config));
+                present_files.extend(all_paths(found));
+            } else if path.is_file() {
+                present_files.insert(path.clone());
+            } else {
+                counts.removed_count += self.forget_everything_under(path, &mut changed_keys);
+            }
+        }
+        let mut present = DiscoveredPaths::default();
+        for path in present_files {
+            self.forget(&path, &mut changed_keys);
+            present.add_file(path, config);
+        }
+
+        // -- ingest-trackers: update the maps; a now-unusable one just leaves its key out
+        if !present.ingest_tracker_paths.is_empty() {
+            let new_trackers: IngestTrackers = scanner.install(|| {
+                helper::read_ingest_trackers(&present.ingest_tracker_paths, scanner.io_throttle())
+            });
+            for path in &present.ingest_tracker_paths {
+                let key: String = helper::parse_key_from_path(path);
+                let previous_pid: Option<String> = self.id_to_pid_map.get(&key).cloned();
+                changed_keys.entry(key).or_insert(previous_pid);
+            }
+            self.id_to_pid_map.extend(new_trackers.id_to_pid_map);
+            self.ingest_info_map.extend(new_trackers.ingest_info_map);
+            self.ingest_tracker_paths.extend(present.ingest_tracker_paths.iter().cloned());
+        }
+        counts.pid_changes = changed_keys
+            .iter()
+            .filter(|(key, previous_pid)| self.id_to_pid_map.get(*key) != previous_pid.as_ref())
+            .count(); // a tracker rewritten with the same pid isn't a change
+
+        // -- ocr-trackers: re-parse, with the updated map
+        let parsed: Vec<(PathBuf, Result<Record, TrackerError>)> = scanner.install(|| {
+            present
+                .ocr_tracker_paths
+                .par_iter()
+                .map(|path| {
+                    let result = helper::process_file(
+                        path,
+                        &self.id_to_pid_map,
+                        &self.resolved_pid_map,
+                        &config.pid_url_base,
+                        scanner.io_throttle(),
+                    );
+                    (path.clone(), result)
+                })
+                .collect()
+        });
+        for (path, result) in parsed {
+            match result {
+                Ok(mut record) => {
+                    let key: String = helper::parse_key_from_path(&path);
+                    helper::join_ingest_info(&mut record, &key, &self.ingest_info_map);
+                    record.validation_warnings = validation::validate(&record);
+                    if let Some(reviews) = scanner.reviews() {
+                        reviews.join(&mut record, &key);
+                    }
+                    self.records.insert(path, record);
+                    counts.parsed_count += 1;
+                }
+                Err(e) => {
+                    log_debug!(tracker_path:% = path.display(), error_kind = e.kind(), error:% = e; "rejecting ocr-tracker");
+                    self.rejected_paths.insert(path);
+                    counts.rejected_count += 1;
+                }
+            }
+        }
+        self.error_tracker_paths.extend(present.error_tracker_paths);
+        self.other_paths.extend(present.other_paths);
+
+        // -- a changed ingest-tracker affects every record with that key, not just the re-parsed ones
+        if !changed_keys.is_empty() {
+            for (path, record) in self.records.iter_mut() {
+                let key: String = helper::parse_key_from_path(path);
+                if changed_keys.contains_key(&key) {
+                    helper::join_pid(
+                        record,
+                        &key,
+                        &self.id_to_pid_map,
+                        &self.resolved_pid_map,
+                        &config.pid_url_base,
+                    );
+                    helper::join_ingest_info(record, &key, &self.ingest_info_map);
+                    if let Some(reviews) = scanner.reviews() {
+                        reviews.join(record, &key); // reviews may be matched by pid
+                    }
+                }
+            }
+        }
+        self.update_summary();
+        counts
+    }
+
+    // drops one path from wherever it's tracked; a dropped ingest-tracker takes its map-entry with it
+    fn forget(&mut self, path: &Path, changed_keys: &mut BTreeMap<String, Option<String>>) -> bool {
+        let was_ingest_tracker = self.ingest_tracker_paths.remove(path);
+        if was_ingest_tracker {
+            let key: String = helper::parse_key_from_path(path);
+            let previous_pid: Option<String> = self.id_to_pid_map.remove(&key);
+            self.ingest_info_map.remove(&key);
+            changed_keys.entry(key).or_insert(previous_pid);
+        }
+        let was_ocr_tracker = self.records.remove(path).is_some() | self.rejected_paths.remove(path);
+        let was_other = self.error_tracker_paths.remove(path) | self.other_paths.remove(path);
+        was_ingest_tracker || was_ocr_tracker || was_other
+    }
+
+    // for a deleted path, which may have been a whole directory; returns how many tracked paths went with it
+    fn forget_everything_under(
+        &mut self, path: &Path, changed_keys: &mut BTreeMap<String, Option<String>>,
+    ) -> usize {
+        let gone: Vec<PathBuf> = self
+            .records
+            .keys()
+            .chain(&self.rejected_paths)
+            .chain(&self.ingest_tracker_paths)
+            .chain(&self.error_tracker_paths)
+            .chain(&self.other_paths)
+            .filter(|tracked| tracked.starts_with(path))
+            .cloned()
+            .collect();
+        gone.iter().filter(|tracked| self.forget(tracked, changed_keys)).count()
+    }
+
+    fn update_summary(&mut self) {
+        // -- as in the scan, the reviews only see the records validation kept
+        let valid_keys: Vec<String> =
+            self.valid_entries().map(|(path, _)| helper::parse_key_from_path(path)).collect();
+        let review_coverage =
+            reviews::coverage(valid_keys.iter().map(String::as_str).zip(self.valid_records()));
+
+        let summary = &mut self.summary;
+        summary.ocr_tracker_count = self.records.len() + self.rejected_paths.len();
+        summary.ingest_tracker_count = self.ingest_tracker_paths.len();
+        summary.error_tracker_count = self.error_tracker_paths.len();
+        summary.other_count = self.other_paths.len();
+        summary.pid_count = self.id_to_pid_map.len();
+        summary.rejected_count = self.rejected_paths.len();
+        summary.validation_failures = validation::count_failures(self.records.values());
+        summary.validation_rejected_count = self.records.len() - valid_keys.len();
+        summary.review_coverage = review_coverage;
+        summary.reviewed_count = summary.review_coverage.values().map(|c| c.reviewed_count).sum();
+        summary.review_excluded_count = match self.reviewed_items {
+            ReviewedItems::Exclude => summary.reviewed_count,
+            _ => 0,
+        };
+        summary.record_count = valid_keys.len() - summary.review_excluded_count;
+        summary.schema_version_counts = schema::count_versions(self.records.values());
+        summary.extra_field_counts = helper::count_extra_fields(self.records.values());
+    }
+}
+
+fn all_paths(paths: DiscoveredPaths) -> impl Iterator<Item = PathBuf> {
+    let categories = [
+        paths.ocr_tracker_paths,
+        paths.ingest_tracker_paths,
+        paths.error_tracker_paths,
+        paths.other_paths,
+    ];
+    categories.into_iter().flatten()
+}
+
+/*  -----------------------------------------------------------------
+    Watches `source_dir` recursively, and hands `on_batch` the paths changed since the last batch.
+    - A batch is sent `debounce` after its first change, so a steady stream of new trackers still
+      gets written out regularly, and a burst of writes to one file is parsed once.
+    - Opens and reads are ignored; creates, modifications, renames, deletes and closes-after-writing count.
+    - Runs until `stop()` returns true (checked at least every `debounce`), or `on_batch` fails.
+    -----------------------------------------------------------------
+*/
+pub fn watch_source_dir(
+    source_dir: &Path, debounce: Duration, stop: impl Fn() -> bool,
+    mut on_batch: impl FnMut(BTreeSet<PathBuf>) -> Result<(), TrackerError>,
+) -> Result<(), TrackerError> {
+    let watch_error = |e: notify::Error| TrackerError::Discovery {
+        path: source_dir.to_path_buf(),
+        source: io::Error::other(e),
+    };
+    let (sender, receiver) = mpsc::channel::<notify::Result<Event>>();
+    // -- events come with absolute paths; they're mapped back under `source_dir`, to match the scan's paths
+    let absolute_source_dir: PathBuf =
+        std::path::absolute(source_dir).map_err(|e| TrackerError::Discovery {
+            path: source_dir.to_path_buf(),
+            source: e,
+        })?;
+    let mut watcher = notify::recommended_watcher(sender).map_err(watch_error)?;
+    watcher.watch(source_dir, RecursiveMode::Recursive).map_err(watch_error)?;
+    log_info!("watching {:?}; batches every {:?}", source_dir, debounce);
+
+    let mut pending: BTreeSet<PathBuf> = BTreeSet::new();
+    let mut batch_started: Option<Instant> = None;
+    while !stop() {
+        let wait: Duration = match batch_started {
+            Some(started) => debounce.saturating_sub(started.elapsed()),
+            None => debounce,
+        };
+        match receiver.recv_timeout(wait) {
+            Ok(Ok(event)) if is_change(&event.kind) => {
+                batch_started.get_or_insert_with(Instant::now);
+                pending.extend(event.paths.into_iter().map(|path| {
+                    match path.strip_prefix(&absolute_source_dir) {
+                        Ok(relative_path) => source_dir.join(relative_path),
+                        Err(_) => path,
+                    }
+                }));
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => log_warn!(error:% = e; "watch-error; some changes may be missed"),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+        if batch_started.is_some_and(|started| started.elapsed() >= debounce) {
+            log_debug!("watch-batch of {} changed paths", pending.len());
+            on_batch(std::mem::take(&mut pending))?;
+            batch_started = None;
+        }
+    }
+    if !pending.is_empty() {
+        on_batch(pending)?; // don't lose the last changes
+    }
+    Ok(())
+}
+
+fn is_change(kind: &EventKind) -> bool {
+    match kind {
+        EventKind::Access(AccessKind::Close(AccessMode::Write)) => true,
+        EventKind::Access(_) => false,
+        _ => true,
+    }
+}
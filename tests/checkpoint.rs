@@ -0,0 +1,93 @@
+mod common;
+
+use common::run_cli;
+use parse_ocr_tracker::checkpoint::{Checkpoint, CHECKPOINT_FILENAME};
+use parse_ocr_tracker::fixtures::{self, FixtureSpec};
+use parse_ocr_tracker::Scanner;
+use serde_json::Value;
+use std::{fs, path::Path};
+use tempfile::TempDir;
+
+// runs the binary over `source` into `output`; returns its json and the csv's bytes
+fn run_scan(source: &Path, output: &Path, extra_args: &[&str]) -> (Value, Vec<u8>) {
+    let (source, output) = (source.to_string_lossy(), output.to_string_lossy());
+    let mut args = vec!["--source_dir_path", &source, "--output_dir_path", &output];
+    args.extend_from_slice(extra_args);
+    let (exit_code, json) = run_cli(&args);
+    assert_eq!(exit_code, Some(0));
+    let csv_bytes = fs::read(json["tracker_output_csv_path"].as_str().unwrap()).unwrap();
+    (json, csv_bytes)
+}
+
+/*  -----------------------------------------------------------------
+    A checkpoint cut off partway (with a torn last line, as from a crash), resumed, gives the same csv
+    as a clean run -- even though the pids changed after the checkpoint was written.
+    -----------------------------------------------------------------
+*/
+#[test]
+fn resumed_run_matches_a_clean_run() {
+    let dir = TempDir::new().unwrap();
+    let source = dir.path().join("tree");
+    let manifest = fixtures::generate(&source, &FixtureSpec::scaled(3, 120)).unwrap();
+
+    // -- the interrupted run: checkpoint everything, then keep only the first half
+    let resumed_output = dir.path().join("resumed");
+    fs::create_dir(&resumed_output).unwrap();
+    let checkpoint = Checkpoint::open(&resumed_output.to_string_lossy(), false, 1).unwrap();
+    Scanner::builder(source.to_string_lossy())
+        .build()
+        .unwrap()
+        .scan_with_checkpoint(Some(&checkpoint))
+        .unwrap();
+    drop(checkpoint);
+    let checkpoint_path = resumed_output.join(CHECKPOINT_FILENAME);
+    let checkpoint_text = fs::read_to_string(&checkpoint_path).unwrap();
+    let lines: Vec<&str> = checkpoint_text.lines().collect();
+    let kept_count: usize = lines.len() / 2;
+    let torn_line: &str = &lines[kept_count][..10];
+    fs::write(
+        &checkpoint_path,
+        format!("{}\n{}", lines[..kept_count].join("\n"), torn_line),
+    )
+    .unwrap();
+
+    // -- the ingest-trackers change before the resume
+    for record in manifest.records.iter().filter(|r| r.pid.is_some()) {
+        let item: &str = record.image_name.trim_end_matches(".jp2");
+        fs::write(
+            source.join(format!("{}/{1}/{1}-ingest_complete.json", &item[..8], item)),
+            format!(r#"{{"pid": "bdr:new_{}"}}"#, item),
+        )
+        .unwrap();
+    }
+
+    let clean_output = dir.path().join("clean");
+    fs::create_dir(&clean_output).unwrap();
+    let (_, clean_csv) = run_scan(&source, &clean_output, &[]);
+    let (resumed_json, resumed_csv) = run_scan(&source, &resumed_output, &["--resume"]);
+    assert_eq!(resumed_json["resumed_from_checkpoint_count"], kept_count);
+    assert!(String::from_utf8_lossy(&resumed_csv).contains("bdr:new_"));
+    assert!(
+        resumed_csv == clean_csv,
+        "the resumed csv differs from the clean run's"
+    );
+    assert!(!checkpoint_path.exists()); // removed once the csv is saved
+}
+
+#[test]
+fn checkpointed_paths_no_longer_in_the_tree_arent_counted() {
+    let dir = TempDir::new().unwrap();
+    let source = dir.path().join("tree");
+    fixtures::generate(&source, &FixtureSpec::scaled(2, 20)).unwrap();
+    let output = dir.path().to_string_lossy().to_string();
+    let scanner = Scanner::builder(source.to_string_lossy()).build().unwrap();
+    let checkpoint = Checkpoint::open(&output, false, 1).unwrap();
+    let first = scanner.scan_with_checkpoint(Some(&checkpoint)).unwrap();
+    drop(checkpoint);
+
+    fs::remove_dir_all(source.join("HH001545")).unwrap();
+    let checkpoint = Checkpoint::open(&output, true, 1).unwrap();
+    let resumed = scanner.scan_with_checkpoint(Some(&checkpoint)).unwrap();
+    assert_eq!(resumed.summary.resumed_count, resumed.summary.ocr_tracker_count);
+    assert!(resumed.summary.resumed_count < first.summary.ocr_tracker_count);
+}
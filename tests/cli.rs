@@ -0,0 +1,89 @@
+mod common;
+
+use common::run_cli;
+use tempfile::TempDir;
+
+#[test]
+fn invalid_datetime_format_is_a_config_error() {
+    let dir = TempDir::new().unwrap();
+    let source = dir.path().to_string_lossy();
+    let (exit_code, json) = run_cli(&[
+        "--source_dir_path",
+        &source,
+        "--output_dir_path",
+        &source,
+        "--datetime_format",
+        "%Q",
+    ]);
+    assert_eq!(exit_code, Some(2));
+    assert_eq!(json["status"], "failed");
+    assert_eq!(json["error"]["kind"], "config");
+    assert_eq!(json["config"]["datetime_format"], "%Q"); // the failing config is still echoed
+}
+
+#[test]
+fn cli_flags_override_the_config_file_which_overrides_the_defaults() {
+    let dir = TempDir::new().unwrap();
+    let source = dir.path().to_string_lossy();
+    let config_path = common::write(
+        dir.path(),
+        "config.toml",
+        "timezone = \"UTC\"\ncheckpoint_every = 7\nextra_columns = [\"dpi\"]\n",
+    );
+    let (exit_code, json) = run_cli(&[
+        "--config",
+        &config_path.to_string_lossy(),
+        "--source_dir_path",
+        &source,
+        "--output_dir_path",
+        &source,
+        "--timezone",
+        "Europe/Paris",
+    ]);
+    assert_eq!(exit_code, Some(0));
+    let config = &json["config"];
+    assert_eq!(config["timezone"], "Europe/Paris"); // cli over file
+    assert_eq!(config["checkpoint_every"], 7); // file over default
+    assert_eq!(config["extra_columns"][0], "dpi");
+    assert_eq!(config["log_backup_count"], 5); // default
+    assert_eq!(config["ocr_tracker_suffix"], "ocr_complete.json");
+}
+
+#[test]
+fn unknown_config_file_keys_are_config_errors() {
+    let dir = TempDir::new().unwrap();
+    let source = dir.path().to_string_lossy();
+    let config_path = common::write(dir.path(), "config.toml", "time_zone = \"UTC\"\n");
+    let (exit_code, json) = run_cli(&[
+        "--config",
+        &config_path.to_string_lossy(),
+        "--source_dir_path",
+        &source,
+        "--output_dir_path",
+        &source,
+    ]);
+    assert_eq!(exit_code, Some(2));
+    assert_eq!(json["error"]["kind"], "config");
+}
+
+#[test]
+fn timezone_sets_the_datestamp_offset() {
+    let dir = TempDir::new().unwrap();
+    let source = dir.path().to_string_lossy();
+    let args = [
+        "--source_dir_path",
+        &source,
+        "--output_dir_path",
+        &source,
+        "--timezone",
+    ];
+
+    let (exit_code, json) = run_cli(&[&args[..], &["UTC"]].concat());
+    assert_eq!(exit_code, Some(0));
+    assert!(json["datetime_stamp"].as_str().unwrap().ends_with("_+00:00"));
+
+    let (exit_code, json) = run_cli(&[&args[..], &["Mars/Olympus_Mons"]].concat());
+    assert_eq!(exit_code, Some(2));
+    assert_eq!(json["error"]["kind"], "config");
+    assert!(json["error"]["message"].as_str().unwrap().contains("Mars/Olympus_Mons"));
+}
@@ -0,0 +1,54 @@
+//! Helpers shared by the integration-tests; each test-file uses only some of them.
+#![allow(dead_code)]
+
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+pub const OCR_JSON: &str = r#"{"orientation": 0, "orientation_conf": 5.2, "script": "Latin", "script_conf": 3.1, "image_name": "HH001545_0001.jp2", "word_count": 120, "avg_confidence": 88.5, "below_90": 30.0, "below_60": 10.0, "below_30": 2.0}"#;
+
+// writes `contents` to `root/relative_path`, creating its dirs; returns the full path
+pub fn write(root: &Path, relative_path: &str, contents: &str) -> PathBuf {
+    let path = root.join(relative_path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+// OCR_JSON, for `item` (e.g. "HH001545_0002")
+pub fn ocr_json(item: &str) -> String {
+    OCR_JSON.replace("HH001545_0001", item)
+}
+
+/*  -----------------------------------------------------------------
+    Writes `item`'s ocr-tracker, and its ingest-tracker if there's a `pid`, at
+    `{collection}/{item}/{item}-ocr_complete.json`, where the collection is the item's first 8 chars.
+    Returns the ocr-tracker's path.
+    -----------------------------------------------------------------
+*/
+pub fn write_item(root: &Path, item: &str, pid: Option<&str>) -> PathBuf {
+    let item_dir = format!("{}/{}", &item[..8], item);
+    if let Some(pid) = pid {
+        write(
+            root,
+            &format!("{}/{}-ingest_complete.json", item_dir, item),
+            &format!(r#"{{"pid": "{}"}}"#, pid),
+        );
+    }
+    write(
+        root,
+        &format!("{}/{}-ocr_complete.json", item_dir, item),
+        &ocr_json(item),
+    )
+}
+
+// runs the binary; returns its exit-code and its stdout as json
+pub fn run_cli(args: &[&str]) -> (Option<i32>, Value) {
+    let output = Command::new(env!("CARGO_BIN_EXE_parse_ocr_tracker")).args(args).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let json: Value = serde_json::from_str(&stdout[stdout.find('{').unwrap()..]).unwrap();
+    (output.status.code(), json)
+}
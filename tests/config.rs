@@ -0,0 +1,56 @@
+use chrono_tz::Tz;
+use parse_ocr_tracker::config::Config;
+use parse_ocr_tracker::TrackerError;
+
+// a config that passes validate(), apart from whatever the test changes
+fn runnable_config() -> Config {
+    Config {
+        source_dir_path: Some("/tmp".to_string()),
+        output_dir_path: Some("/tmp".to_string()),
+        ..Config::default()
+    }
+}
+
+#[test]
+fn utc_and_iana_names_are_valid_timezones() {
+    for (name, expected) in [
+        ("UTC", Tz::UTC),
+        ("utc", Tz::UTC),
+        ("US/Eastern", Tz::US__Eastern),
+        ("America/Chicago", Tz::America__Chicago),
+    ] {
+        let config = Config {
+            timezone: name.to_string(),
+            ..runnable_config()
+        };
+        assert_eq!(config.timezone().unwrap(), expected);
+        assert!(config.validate().is_ok());
+    }
+}
+
+#[test]
+fn bad_timezones_and_strftime_formats_are_config_errors() {
+    let bad_configs = [
+        Config {
+            timezone: "Mars/Olympus_Mons".to_string(),
+            ..runnable_config()
+        },
+        Config {
+            timezone: String::new(),
+            ..runnable_config()
+        },
+        Config {
+            datetime_format: "%Y-%m-%d %Q".to_string(),
+            ..runnable_config()
+        },
+        Config {
+            filename_datetime_format: "%".to_string(),
+            ..runnable_config()
+        },
+    ];
+    for config in bad_configs {
+        let error = config.validate().unwrap_err();
+        assert!(matches!(error, TrackerError::Config(_)), "{:?}", error);
+        assert_eq!(error.exit_code(), 2);
+    }
+}
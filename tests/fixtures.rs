@@ -0,0 +1,86 @@
+use parse_ocr_tracker::fixtures::{self, FixtureSpec};
+use parse_ocr_tracker::{Scanner, TrackerError};
+use tempfile::TempDir;
+
+#[test]
+fn scan_matches_the_manifest() {
+    let dir = TempDir::new().unwrap();
+    let spec = FixtureSpec::scaled(4, 300);
+    let manifest = fixtures::generate(dir.path(), &spec).unwrap();
+    let results = Scanner::builder(dir.path().to_string_lossy()).build().unwrap().scan().unwrap();
+
+    let summary = &results.summary;
+    assert_eq!(summary.ocr_tracker_count, manifest.ocr_tracker_count);
+    assert_eq!(summary.ingest_tracker_count, manifest.ingest_tracker_count);
+    assert_eq!(summary.error_tracker_count, manifest.error_tracker_count);
+    assert_eq!(summary.other_count, manifest.other_count);
+    assert_eq!(summary.pid_count, manifest.pid_count);
+    assert_eq!(summary.record_count, manifest.record_count);
+    assert_eq!(summary.rejected_count, manifest.rejected_count);
+
+    let mut rejected_paths: Vec<String> = results
+        .rejected_paths
+        .iter()
+        .map(|path| path.strip_prefix(dir.path()).unwrap().to_string_lossy().replace('\\', "/"))
+        .collect();
+    rejected_paths.sort();
+    assert_eq!(rejected_paths, manifest.rejected_paths);
+
+    let mut records = results.records.clone();
+    records.sort_by(|a, b| a.image_name.cmp(&b.image_name));
+    assert_eq!(records.len(), manifest.records.len());
+    for (record, expected) in records.iter().zip(&manifest.records) {
+        assert_eq!(record.image_name, expected.image_name);
+        assert_eq!(record.pid, expected.pid);
+        assert_eq!(record.script == "-", expected.has_placeholders);
+    }
+}
+
+#[test]
+fn same_seed_builds_the_same_tree() {
+    let (first, second) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+    let spec = FixtureSpec::default();
+    let first_manifest = fixtures::generate(first.path(), &spec).unwrap();
+    let second_manifest = fixtures::generate(second.path(), &spec).unwrap();
+    assert_eq!(
+        serde_json::to_value(&first_manifest).unwrap(),
+        serde_json::to_value(&second_manifest).unwrap()
+    );
+
+    let tracker = "HH001545/HH001545_0001/HH001545_0001-ocr_complete.json";
+    assert_eq!(
+        std::fs::read(first.path().join(tracker)).unwrap(),
+        std::fs::read(second.path().join(tracker)).unwrap()
+    );
+}
+
+#[test]
+fn scaled_specs_are_valid_at_small_sizes() {
+    for items in 0..=20 {
+        let dir = TempDir::new().unwrap();
+        let spec = FixtureSpec::scaled(2, items);
+        let manifest = fixtures::generate(dir.path(), &spec)
+            .unwrap_or_else(|e| panic!("scaled(2, {}) didn't generate: {}", items, e));
+        let results = Scanner::builder(dir.path().to_string_lossy()).build().unwrap().scan().unwrap();
+        assert_eq!(results.summary.record_count, manifest.record_count);
+    }
+}
+
+#[test]
+fn bad_specs_and_non_empty_dirs_are_config_errors() {
+    let dir = TempDir::new().unwrap();
+    let spec = FixtureSpec {
+        ingest_trackers: 101,
+        ..FixtureSpec::default()
+    };
+    assert!(matches!(
+        fixtures::generate(dir.path(), &spec),
+        Err(TrackerError::Config(_))
+    ));
+
+    fixtures::generate(dir.path(), &FixtureSpec::default()).unwrap();
+    assert!(matches!(
+        fixtures::generate(dir.path(), &FixtureSpec::default()),
+        Err(TrackerError::Config(_))
+    ));
+}
@@ -0,0 +1,62 @@
+use chrono::{TimeZone, Utc};
+use parse_ocr_tracker::fixtures::{self, FixtureSpec};
+use parse_ocr_tracker::metrics::{self, OutputSeconds};
+use parse_ocr_tracker::Scanner;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn metrics_file_has_the_counts_and_a_cumulative_histogram() {
+    let tree = TempDir::new().unwrap();
+    let manifest = fixtures::generate(tree.path(), &FixtureSpec::scaled(2, 20)).unwrap();
+    let results = Scanner::builder(tree.path().to_string_lossy()).build().unwrap().scan().unwrap();
+    let finished_at = Utc.with_ymd_and_hms(2024, 4, 1, 12, 0, 0).unwrap();
+    let text = metrics::render(
+        &results.summary,
+        &results.records,
+        &OutputSeconds::default(),
+        finished_at,
+    );
+
+    let value = |sample: &str| -> f64 {
+        let line = text.lines().find(|line| line.starts_with(&format!("{} ", sample))).unwrap();
+        line.rsplit(' ').next().unwrap().parse().unwrap()
+    };
+    assert_eq!(
+        value("parse_ocr_tracker_last_run_timestamp_seconds"),
+        1711972800.0
+    );
+    assert_eq!(value("parse_ocr_tracker_last_run_complete"), 1.0);
+    assert_eq!(
+        value(r#"parse_ocr_tracker_discovered_files{category="ocr_tracker"}"#),
+        manifest.ocr_tracker_count as f64
+    );
+    assert_eq!(value("parse_ocr_tracker_records"), manifest.record_count as f64);
+    assert_eq!(
+        value("parse_ocr_tracker_rejected_files"),
+        manifest.rejected_count as f64
+    );
+    let missing_pids = manifest.records.iter().filter(|r| r.pid.is_none()).count();
+    assert_eq!(value("parse_ocr_tracker_missing_pids"), missing_pids as f64);
+    assert!(text.contains(r#"parse_ocr_tracker_stage_duration_seconds{stage="process_files"}"#));
+
+    // -- buckets are cumulative, ending at the record-count
+    let buckets: Vec<f64> = text
+        .lines()
+        .filter(|line| line.starts_with("parse_ocr_tracker_avg_confidence_bucket"))
+        .map(|line| line.rsplit(' ').next().unwrap().parse().unwrap())
+        .collect();
+    assert!(buckets.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert_eq!(*buckets.last().unwrap(), manifest.record_count as f64);
+    assert_eq!(
+        value("parse_ocr_tracker_avg_confidence_count"),
+        manifest.record_count as f64
+    );
+
+    // -- written whole, with no temp-file left behind
+    let out = TempDir::new().unwrap();
+    let metrics_path = out.path().join("parse_ocr_tracker.prom");
+    metrics::write_metrics_file(&metrics_path, &text).unwrap();
+    assert_eq!(fs::read_to_string(&metrics_path).unwrap(), text);
+    assert_eq!(fs::read_dir(out.path()).unwrap().count(), 1);
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use common::write_item;
+use parse_ocr_tracker::{PidSource, Scanner, TrackerError};
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+};
+use tempfile::TempDir;
+
+/*  -----------------------------------------------------------------
+    A stub search-api on a local port, answering `GET /search?id={id}`:
+    - HH000001_0002: found, as `bdr:resolved2`
+    - HH000001_0003: a 500
+    - anything else: an empty search-result
+    Returns the url-template and a count of the requests it has answered.
+    -----------------------------------------------------------------
+*/
+fn stub_search_api() -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url_template = format!("http://{}/search?id={{id}}", listener.local_addr().unwrap());
+    let request_count = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&request_count);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            loop {
+                let mut header = String::new();
+                if reader.read_line(&mut header).unwrap() == 0 || header == "\r\n" {
+                    break;
+                }
+            }
+            counter.fetch_add(1, Ordering::SeqCst);
+            let (status, body) = if request_line.contains("id=HH000001_0002 ") {
+                ("200 OK", r#"{"response": {"docs": [{"pid": "bdr:resolved2"}]}}"#)
+            } else if request_line.contains("id=HH000001_0003 ") {
+                ("500 Internal Server Error", "oops")
+            } else {
+                ("200 OK", r#"{"response": {"docs": []}}"#)
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    (url_template, request_count)
+}
+
+// four items; only the first has an ingest-tracker
+fn resolver_tree() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    for n in 1..=4 {
+        let pid = (n == 1).then_some("bdr:1");
+        write_item(dir.path(), &format!("HH000001_000{}", n), pid);
+    }
+    dir
+}
+
+#[test]
+fn resolver_fills_in_missing_pids_and_caches_them() {
+    let dir = resolver_tree();
+    let cache_dir = TempDir::new().unwrap();
+    let cache_path = cache_dir.path().join("pid_cache.json");
+    let (url_template, request_count) = stub_search_api();
+    let scanner = Scanner::builder(dir.path().to_string_lossy())
+        .pid_resolver_url(&url_template)
+        .pid_resolver_cache_path(cache_path.to_string_lossy())
+        .pid_resolver_concurrency(2)
+        .build()
+        .unwrap();
+
+    let results = scanner.scan().unwrap();
+    assert_eq!(request_count.load(Ordering::SeqCst), 3); // HH000001_0001 has an ingest-tracker
+    assert_eq!(results.summary.pid_count, 1);
+    assert_eq!(results.summary.resolved_pid_count, 1);
+    assert_eq!(results.summary.resolver_failed_count, 1);
+    let record = |image_name: &str| results.records.iter().find(|r| r.image_name == image_name).unwrap();
+    assert_eq!(record("HH000001_0001.jp2").pid_source, Some(PidSource::Tracker));
+    assert_eq!(record("HH000001_0002.jp2").pid.as_deref(), Some("bdr:resolved2"));
+    assert_eq!(record("HH000001_0002.jp2").pid_source, Some(PidSource::Resolver));
+    assert_eq!(record("HH000001_0003.jp2").pid_source, None);
+    assert_eq!(record("HH000001_0004.jp2").pid, None);
+
+    let cache: serde_json::Value = serde_json::from_slice(&fs::read(&cache_path).unwrap()).unwrap();
+    assert_eq!(cache, serde_json::json!({"HH000001_0002": "bdr:resolved2"}));
+
+    // -- a rerun only asks about the keys that weren't found
+    let results = scanner.scan().unwrap();
+    assert_eq!(request_count.load(Ordering::SeqCst), 5);
+    assert_eq!(results.resolved_pid_map.len(), 1);
+}
+
+#[test]
+fn resolver_options_are_checked() {
+    let error = Scanner::builder("/tmp").pid_resolver_url("http://localhost/search").build().err().unwrap();
+    assert!(matches!(error, TrackerError::Config(_)));
+    let error = Scanner::builder("/tmp")
+        .pid_resolver_url("http://localhost/search?id={id}")
+        .pid_resolver_concurrency(0)
+        .build()
+        .err()
+        .unwrap();
+    assert!(matches!(error, TrackerError::Config(_)));
+}
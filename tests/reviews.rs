@@ -0,0 +1,82 @@
+mod common;
+
+use common::{write, write_item};
+use parse_ocr_tracker::reviews::ReviewedItems;
+use parse_ocr_tracker::{Scanner, TrackerError};
+use tempfile::TempDir;
+
+/*  -----------------------------------------------------------------
+    Three items in two collections, each with a pid; and a reviews-csv that matches
+    HH001545_0001 by key and HH001546_0001 by pid.
+    -----------------------------------------------------------------
+*/
+fn reviewed_tree() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    for (n, item) in ["HH001545_0001", "HH001545_0002", "HH001546_0001"].iter().enumerate() {
+        write_item(&dir.path().join("tree"), item, Some(&format!("bdr:{}", n + 1)));
+    }
+    write(
+        dir.path(),
+        "reviews.csv",
+        "key,pid,status,reviewer,date\nHH001545_0001,,ok,jdoe,2024-05-01\n,bdr:3,needs-rotation,,\n",
+    );
+    dir
+}
+
+fn scanner(dir: &TempDir, reviewed_items: ReviewedItems) -> Scanner {
+    Scanner::builder(dir.path().join("tree").to_string_lossy())
+        .reviews_path(dir.path().join("reviews.csv").to_string_lossy())
+        .reviewed_items(reviewed_items)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn reviews_are_joined_and_downranked() {
+    let dir = reviewed_tree();
+    let results = scanner(&dir, ReviewedItems::Downrank).scan().unwrap();
+    let order: Vec<&str> = results.records.iter().map(|r| r.image_name.as_str()).collect();
+    assert_eq!(
+        order,
+        ["HH001545_0002.jp2", "HH001545_0001.jp2", "HH001546_0001.jp2"]
+    );
+    assert_eq!(results.records[1].review_status.as_deref(), Some("ok"));
+    assert_eq!(results.records[1].reviewer.as_deref(), Some("jdoe"));
+    assert_eq!(
+        results.records[2].review_status.as_deref(),
+        Some("needs-rotation")
+    );
+    assert_eq!(results.records[0].review_status, None);
+    assert_eq!(results.summary.reviewed_count, 2);
+    assert_eq!(results.summary.review_excluded_count, 0);
+
+    let coverage = &results.summary.review_coverage;
+    assert_eq!(
+        (
+            coverage["HH001545"].item_count,
+            coverage["HH001545"].reviewed_count
+        ),
+        (2, 1)
+    );
+    assert_eq!(coverage["HH001546"].status_counts["needs-rotation"], 1);
+}
+
+#[test]
+fn reviewed_records_can_be_excluded() {
+    let dir = reviewed_tree();
+    let results = scanner(&dir, ReviewedItems::Exclude).scan().unwrap();
+    assert_eq!(results.records.len(), 1);
+    assert_eq!(results.records[0].image_name, "HH001545_0002.jp2");
+    assert_eq!(results.excluded_records.len(), 2);
+    assert_eq!(results.summary.record_count, 1);
+    assert_eq!(results.summary.review_excluded_count, 2);
+
+    // -- a row with neither a key nor a pid is a config-error
+    write(dir.path(), "reviews.csv", "key,pid,status\n,,ok\n");
+    let error = Scanner::builder(dir.path().join("tree").to_string_lossy())
+        .reviews_path(dir.path().join("reviews.csv").to_string_lossy())
+        .build()
+        .err()
+        .unwrap();
+    assert!(matches!(error, TrackerError::Config(_)));
+}
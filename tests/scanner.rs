@@ -0,0 +1,376 @@
+mod common;
+
+use common::{write, OCR_JSON};
+use parse_ocr_tracker::schema::SchemaVersion;
+use parse_ocr_tracker::{helper, ScanStatus, Scanner, TrackerError};
+use std::{
+    fs,
+    io::{self, Write},
+};
+use tempfile::TempDir;
+
+const DASH_OCR_JSON: &str = r#"{"orientation": "-", "orientation_conf": "-", "script": "-", "script_conf": "-", "image_name": "HH001545_0002.jp2", "word_count": 0, "avg_confidence": 0.0, "below_90": 0.0, "below_60": 0.0, "below_30": 0.0}"#;
+
+/*  -----------------------------------------------------------------
+    Builds a small Hall-Hoag-shaped tree:
+    - HH001545_0001: ocr-tracker and ingest-tracker (pid bdr:1001)
+    - HH001545_0002: dash-valued ocr-tracker, malformed ingest-tracker, and an error-tracker
+    - HH001545: an organization-file named like an ocr-tracker, and a notes file
+    -----------------------------------------------------------------
+*/
+fn fixture_tree() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path();
+    write(
+        root,
+        "HH001545/HH001545_0001/HH001545_0001-ocr_complete.json",
+        OCR_JSON,
+    );
+    write(
+        root,
+        "HH001545/HH001545_0001/HH001545_0001-ingest_complete.json",
+        r#"{"pid": "bdr:1001"}"#,
+    );
+    write(
+        root,
+        "HH001545/HH001545_0002/HH001545_0002-ocr_complete.json",
+        DASH_OCR_JSON,
+    );
+    write(
+        root,
+        "HH001545/HH001545_0002/HH001545_0002-ingest_complete.json",
+        "{bad",
+    );
+    write(
+        root,
+        "HH001545/HH001545_0002/HH001545_0002-ocr_error.json",
+        r#"{"error": "timeout"}"#,
+    );
+    write(root, "HH001545/HH001545-ocr_complete.json", r#"{"items": []}"#);
+    write(root, "HH001545/notes.txt", "notes");
+    dir
+}
+
+#[test]
+fn discover_classifies_paths() {
+    let dir = fixture_tree();
+    let scanner = Scanner::builder(dir.path().to_string_lossy()).build().unwrap();
+    let paths = scanner.discover().unwrap();
+    assert_eq!(paths.ocr_tracker_paths.len(), 3);
+    assert_eq!(paths.ingest_tracker_paths.len(), 2);
+    assert_eq!(paths.error_tracker_paths.len(), 1);
+    assert_eq!(paths.other_paths.len(), 1);
+    assert!(paths.ocr_tracker_paths.windows(2).all(|pair| pair[0] <= pair[1]));
+}
+
+#[test]
+fn scan_joins_pids_and_rejects_organization_files() {
+    let dir = fixture_tree();
+    let scanner = Scanner::builder(dir.path().to_string_lossy())
+        .pid_url_base("https://example.org/item/")
+        .threads(2)
+        .build()
+        .unwrap();
+    let results = scanner.scan().unwrap();
+
+    assert_eq!(results.summary.status, ScanStatus::Complete);
+    assert_eq!(results.summary.record_count, 2);
+    assert_eq!(results.summary.rejected_count, 1);
+    assert_eq!(results.summary.pid_count, 1); // the malformed ingest-tracker is skipped
+    assert_eq!(results.summary.thread_count, 2);
+    assert!(results.rejected_paths[0].ends_with("HH001545/HH001545-ocr_complete.json"));
+
+    let with_pid = results.records.iter().find(|r| r.image_name == "HH001545_0001.jp2").unwrap();
+    assert_eq!(with_pid.pid.as_deref(), Some("bdr:1001"));
+    assert_eq!(
+        with_pid.pid_url.as_deref(),
+        Some(" https://example.org/item/bdr:1001/")
+    );
+    assert_eq!(with_pid.word_count, 120);
+
+    let dashed = results.records.iter().find(|r| r.image_name == "HH001545_0002.jp2").unwrap();
+    assert_eq!(dashed.orientation, 0);
+    assert_eq!(dashed.script, "-");
+    assert_eq!(dashed.pid, None);
+}
+
+#[test]
+fn classification_rules_are_configurable() {
+    let dir = TempDir::new().unwrap();
+    write(dir.path(), "AB0001/AB0001_0001/AB0001_0001-ocr.json", OCR_JSON);
+    write(
+        dir.path(),
+        "AB0001/AB0001_0001/AB0001_0001-ingest.json",
+        r#"{"pid": "bdr:2002"}"#,
+    );
+    write(
+        dir.path(),
+        "AB0001/AB0001_0001/AB0001_0001-ocr_complete.json",
+        OCR_JSON,
+    );
+    write(dir.path(), "AB0001/AB0001_0001/AB0001_0001.failed.json", "{}");
+
+    let scanner = Scanner::builder(dir.path().to_string_lossy())
+        .ocr_tracker_suffix("-ocr.json")
+        .ingest_tracker_suffix("-ingest.json")
+        .error_tracker_marker("failed")
+        .build()
+        .unwrap();
+    let results = scanner.scan().unwrap();
+    assert_eq!(results.summary.ocr_tracker_count, 1);
+    assert_eq!(results.summary.ingest_tracker_count, 1);
+    assert_eq!(results.summary.error_tracker_count, 1);
+    assert_eq!(results.summary.other_count, 1); // the default-named tracker is just another file now
+    assert_eq!(results.records[0].pid.as_deref(), Some("bdr:2002"));
+}
+
+#[test]
+fn empty_tree_scans_cleanly() {
+    let dir = TempDir::new().unwrap();
+    let results = Scanner::builder(dir.path().to_string_lossy()).build().unwrap().scan().unwrap();
+    assert_eq!(results.summary.status, ScanStatus::Complete);
+    assert!(results.records.is_empty());
+    assert!(results.id_to_pid_map.is_empty());
+}
+
+#[test]
+fn builder_rejects_bad_options() {
+    assert!(Scanner::builder("").build().is_err());
+    assert!(Scanner::builder("/tmp").threads(0).build().is_err());
+    assert!(Scanner::builder("/tmp").max_concurrent_reads(0).build().is_err());
+    assert!(Scanner::builder("/tmp").files_per_second(-1.0).build().is_err());
+}
+
+#[test]
+fn missing_source_dir_is_a_discovery_error() {
+    let dir = TempDir::new().unwrap();
+    let missing = dir.path().join("not_mounted");
+    let error = Scanner::builder(missing.to_string_lossy()).build().unwrap().scan().unwrap_err();
+    assert!(matches!(error, TrackerError::Discovery { .. }));
+    assert_eq!(error.exit_code(), 3);
+    assert_eq!(error.path(), Some(missing.as_path()));
+    assert_eq!(error.to_json()["kind"], "discovery");
+}
+
+#[test]
+fn bad_options_are_config_errors() {
+    let error = Scanner::builder("/tmp").threads(0).build().err().unwrap();
+    assert!(matches!(error, TrackerError::Config(_)));
+    assert_eq!(error.exit_code(), 2);
+}
+
+#[test]
+fn escaped_and_unusable_pids() {
+    let dir = TempDir::new().unwrap();
+    write(
+        dir.path(),
+        "HH000001/HH000001_0001/HH000001_0001-ocr_complete.json",
+        OCR_JSON,
+    );
+    write(
+        dir.path(),
+        "HH000001/HH000001_0001/HH000001_0001-ingest_complete.json",
+        r#"{"pid": "bdr:\u00312"}"#, // json-escapes in the pid are decoded
+    );
+    write(
+        dir.path(),
+        "HH000001/HH000001_0002/HH000001_0002-ingest_complete.json",
+        r#"{"pid": " "}"#,
+    );
+    write(
+        dir.path(),
+        "HH000001/HH000001_0003/HH000001_0003-ingest_complete.json",
+        r#"{"pid": 42}"#,
+    );
+
+    write(
+        dir.path(),
+        "HH000001/HH000001_0004/-ingest_complete.json", // no item-key in the name
+        r#"{"pid": "bdr:stray"}"#,
+    );
+
+    let results = Scanner::builder(dir.path().to_string_lossy()).build().unwrap().scan().unwrap();
+    assert_eq!(results.summary.ingest_tracker_count, 4);
+    assert_eq!(results.id_to_pid_map.len(), 2); // the non-string and key-less ones are skipped; the blank one is kept, as-is
+    assert!(!results.id_to_pid_map.contains_key(""));
+    assert_eq!(results.id_to_pid_map["HH000001_0002"], " ");
+    assert_eq!(results.records[0].pid.as_deref(), Some("bdr:12"));
+}
+
+#[test]
+fn ingest_fields_are_joined_onto_records() {
+    let dir = fixture_tree();
+    write(
+        dir.path(),
+        "HH001545/HH001545_0001/HH001545_0001-ingest_complete.json",
+        r#"{"pid": "bdr:1001", "ingest_date": "2024-03-02T10:00:00", "batch": 42, "collection": "bdr:hh", "parent_pid": null, "operator": "kim"}"#,
+    );
+    let results = Scanner::builder(dir.path().to_string_lossy()).build().unwrap().scan().unwrap();
+    let info = &results.ingest_info_map["HH001545_0001"];
+    assert_eq!(info.batch_id.as_deref(), Some("42")); // numbers become text
+    assert_eq!(info.parent_pid, None);
+
+    let record = results.records.iter().find(|r| r.image_name == "HH001545_0001.jp2").unwrap();
+    assert_eq!(record.pid.as_deref(), Some("bdr:1001"));
+    assert_eq!(record.ingest_timestamp.as_deref(), Some("2024-03-02T10:00:00"));
+    assert_eq!(record.ingest_batch_id.as_deref(), Some("42"));
+    assert_eq!(record.ingest_collection_id.as_deref(), Some("bdr:hh"));
+    assert_eq!(record.ingest_extra.as_deref(), Some(r#"{"operator":"kim"}"#));
+
+    // -- an item whose ingest-tracker has only the pid gets empty columns
+    let other = results.records.iter().find(|r| r.image_name == "HH001545_0002.jp2").unwrap();
+    assert_eq!(
+        (other.ingest_timestamp.as_deref(), other.ingest_extra.as_deref()),
+        (None, None)
+    );
+}
+
+#[test]
+fn extra_ocr_fields_are_kept_and_can_be_promoted() {
+    let dir = fixture_tree();
+    write(
+        dir.path(),
+        "HH001545/HH001545_0001/HH001545_0001-ocr_complete.json",
+        &OCR_JSON.replace(
+            "}",
+            r#", "engine_version": "5.3.0", "dpi": 300, "layout": {"columns": 2}}"#,
+        ),
+    );
+    let results = Scanner::builder(dir.path().to_string_lossy()).build().unwrap().scan().unwrap();
+    let counts = &results.summary.extra_field_counts;
+    assert_eq!(
+        (counts["engine_version"], counts["dpi"], counts["layout"]),
+        (1, 1, 1)
+    );
+    let record = results.records.iter().find(|r| r.image_name == "HH001545_0001.jp2").unwrap();
+    assert_eq!(record.extra["dpi"], 300);
+
+    // -- promoted fields become trailing csv-columns; records without them get empty cells
+    let extra_columns: Vec<String> = vec!["engine_version".to_string(), "layout".to_string()];
+    let mut csv_bytes: Vec<u8> = Vec::new();
+    helper::write_records_csv(&results.records, &extra_columns, &mut csv_bytes).unwrap();
+    let csv_text = String::from_utf8(csv_bytes).unwrap();
+    let lines: Vec<&str> = csv_text.lines().collect();
+    assert!(lines[0].ends_with(",validation_warnings,engine_version,layout"));
+    assert!(lines[1].ends_with(r#",5.3.0,"{""columns"":2}""#));
+    assert!(lines[2].ends_with(",,"));
+    assert!(!csv_text.contains("dpi")); // not promoted
+}
+
+#[test]
+fn extra_fields_named_like_columns_are_renamed() {
+    let dir = fixture_tree();
+    write(
+        dir.path(),
+        "HH001545/HH001545_0001/HH001545_0001-ocr_complete.json",
+        &OCR_JSON.replace(
+            "}",
+            r#", "reviewer": "someone", "pid": "bdr:x", "ocr_pid": "kept"}"#,
+        ),
+    );
+    let results = Scanner::builder(dir.path().to_string_lossy()).build().unwrap().scan().unwrap();
+    let record = results.records.iter().find(|r| r.image_name == "HH001545_0001.jp2").unwrap();
+    assert_eq!(record.reviewer, None);
+    assert_eq!(record.pid.as_deref(), Some("bdr:1001")); // from the ingest-tracker, as usual
+    assert_eq!(record.extra["ocr_reviewer"], "someone");
+    assert_eq!(record.extra["ocr_pid"], "kept");
+    assert_eq!(record.extra["ocr_ocr_pid"], "bdr:x");
+
+    // -- every row still has every column
+    let mut csv_bytes: Vec<u8> = Vec::new();
+    helper::write_records_csv(&results.records, &["ocr_reviewer".to_string()], &mut csv_bytes).unwrap();
+    let mut reader = csv::Reader::from_reader(csv_bytes.as_slice());
+    let column_count: usize = reader.headers().unwrap().len();
+    for row in reader.records() {
+        assert_eq!(row.unwrap().len(), column_count);
+    }
+}
+
+#[test]
+fn schema_versions_are_detected_or_declared() {
+    let dir = fixture_tree();
+    let with_version =
+        |json: &str, version: &str| json.replace("}", &format!(r#", "schema_version": {}}}"#, version));
+    for (item, contents) in [
+        ("HH001545_0003", with_version(OCR_JSON, r#""v2""#)), // declared, though dash-free
+        ("HH001545_0004", with_version(OCR_JSON, "3")),       // unknown version
+        ("HH001545_0005", with_version(DASH_OCR_JSON, "1")),  // dashes aren't allowed in v1
+    ] {
+        write(
+            dir.path(),
+            &format!("HH001545/{0}/{0}-ocr_complete.json", item),
+            &contents.replace("HH001545_0001", item).replace("HH001545_0002", item),
+        );
+    }
+    let results = Scanner::builder(dir.path().to_string_lossy()).build().unwrap().scan().unwrap();
+    let version_of = |image_name: &str| {
+        let record = results.records.iter().find(|r| r.image_name == image_name).unwrap();
+        record.schema_version
+    };
+    assert_eq!(version_of("HH001545_0001.jp2"), Some(SchemaVersion::V1));
+    assert_eq!(version_of("HH001545_0002.jp2"), Some(SchemaVersion::V2));
+    assert_eq!(version_of("HH001545_0003.jp2"), Some(SchemaVersion::V2));
+    assert!(results.records.iter().all(|r| !r.extra.contains_key("schema_version"))); // consumed, not an extra
+    assert_eq!(results.summary.rejected_count, 3); // the organization-file, and the two misfits
+    let counts = &results.summary.schema_version_counts;
+    assert_eq!((counts[&SchemaVersion::V1], counts[&SchemaVersion::V2]), (1, 2));
+}
+
+#[cfg(unix)]
+#[test]
+fn broken_symlinks_are_walk_errors() {
+    let dir = fixture_tree();
+    let broken = dir.path().join("HH001545/HH001545_0003");
+    std::os::unix::fs::symlink(dir.path().join("not_mounted"), &broken).unwrap();
+
+    let results = Scanner::builder(dir.path().to_string_lossy()).build().unwrap().scan().unwrap();
+    assert_eq!(results.summary.walk_errors.len(), 1);
+    assert_eq!(
+        results.summary.walk_errors[0].path.as_deref(),
+        Some(broken.as_path())
+    );
+    assert_eq!(results.summary.record_count, 2); // the rest of the tree is still scanned
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinked_trackers_are_followed() {
+    let dir = fixture_tree();
+    let elsewhere = TempDir::new().unwrap();
+    write(
+        elsewhere.path(),
+        "HH001545_0009/HH001545_0009-ocr_complete.json",
+        OCR_JSON,
+    );
+    std::os::unix::fs::symlink(
+        elsewhere.path().join("HH001545_0009"),
+        dir.path().join("HH001545/HH001545_0009"),
+    )
+    .unwrap();
+
+    let paths = Scanner::builder(dir.path().to_string_lossy()).build().unwrap().discover().unwrap();
+    assert_eq!(paths.ocr_tracker_paths.len(), 4);
+    assert!(paths.walk_errors.is_empty());
+}
+
+#[test]
+fn failed_atomic_writes_leave_no_temp_file() {
+    let dir = TempDir::new().unwrap();
+    let path = write(dir.path(), "summary.json", "{}");
+    let error = helper::write_atomically(&path, |file| {
+        file.write_all(b"{\"half\": ")?;
+        Err(io::Error::other("disk full"))
+    })
+    .unwrap_err();
+    assert!(matches!(error, TrackerError::Output { .. }));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "{}"); // the old contents are untouched
+    assert!(!dir.path().join("summary.json.tmp").exists());
+
+    // -- a failed rename (here, onto a directory) is cleaned up too
+    let dir_path = write(dir.path(), "a_dir/inside.txt", "").parent().unwrap().to_path_buf();
+    assert!(helper::write_atomically(&dir_path, |file| file.write_all(b"{}")).is_err());
+    assert!(!dir.path().join("a_dir.tmp").exists());
+
+    helper::write_atomically(&path, |file| file.write_all(b"{\"whole\": true}")).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "{\"whole\": true}");
+}
@@ -0,0 +1,181 @@
+mod common;
+
+use common::{ocr_json, run_cli, write, write_item};
+use parse_ocr_tracker::fixtures::{self, FixtureSpec};
+use parse_ocr_tracker::server::{ResultsServer, ServedScan};
+use parse_ocr_tracker::{helper, Scanner};
+use serde_json::{json, Value};
+use std::{path::Path, thread};
+use tempfile::TempDir;
+
+// scans a generated tree and serves it on a free local port; returns the base-url
+fn serve_fixture_scan() -> (String, TempDir) {
+    let dir = TempDir::new().unwrap();
+    fixtures::generate(dir.path(), &FixtureSpec::scaled(3, 60)).unwrap();
+    let results = Scanner::builder(dir.path().to_string_lossy()).build().unwrap().scan().unwrap();
+    let summary = json!({ "ocr_data_vector_count": results.records.len() });
+    (
+        serve(ServedScan::new(
+            results.records,
+            &results.record_paths,
+            Vec::new(),
+            summary,
+        )),
+        dir,
+    )
+}
+
+// serves `served_scan` on a free local port; returns the base-url
+fn serve(served_scan: ServedScan) -> String {
+    let server = ResultsServer::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", server.local_addr().unwrap());
+    thread::spawn(move || server.run(&served_scan));
+    base_url
+}
+
+fn get_json(url: &str) -> Value {
+    let body: String = ureq::get(url).call().unwrap().body_mut().read_to_string().unwrap();
+    serde_json::from_str(&body).unwrap()
+}
+
+#[test]
+fn items_are_filtered_sorted_and_paged() {
+    let (base_url, _dir) = serve_fixture_scan();
+    assert_eq!(
+        get_json(&format!("{}/summary", base_url))["ocr_data_vector_count"],
+        59
+    );
+
+    let all = get_json(&format!("{}/items?limit=1000", base_url));
+    assert_eq!(all["total"], 59);
+
+    let page = get_json(&format!(
+        "{}/items?where=word_count%3E0&where=avg_confidence%3C90&sort=-avg_confidence,image_name&offset=1&limit=3",
+        base_url
+    ));
+    let items = page["items"].as_array().unwrap();
+    assert_eq!(items.len(), 3);
+    assert!(page["total"].as_u64().unwrap() > 3);
+    let confidences: Vec<f64> = items.iter().map(|item| item["avg_confidence"].as_f64().unwrap()).collect();
+    assert!(confidences.windows(2).all(|pair| pair[0] >= pair[1]));
+    assert!(confidences.iter().all(|&confidence| confidence < 90.0));
+
+    let placeholders = get_json(&format!("{}/items?where=script=-", base_url));
+    assert!(placeholders["items"].as_array().unwrap().iter().all(|item| item["orientation"] == 0));
+}
+
+#[test]
+fn single_items_collections_and_csv() {
+    let (base_url, _dir) = serve_fixture_scan();
+    let first = get_json(&format!("{}/items?sort=image_name&limit=1", base_url))["items"][0].clone();
+    let key = first["image_name"].as_str().unwrap().trim_end_matches(".jp2").to_string();
+    assert_eq!(get_json(&format!("{}/items/{}", base_url, key)), first);
+
+    let rollup = get_json(&format!("{}/collections/HH001545", base_url));
+    assert!(rollup["record_count"].as_u64().unwrap() > 0);
+    assert!(rollup["avg_confidence"]["max"].as_f64().unwrap() <= 97.0);
+
+    let mut response = ureq::get(&format!("{}/items.csv?where=pid_source=tracker", base_url)).call().unwrap();
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/csv"));
+    let csv_text: String = response.body_mut().read_to_string().unwrap();
+    assert!(csv_text.starts_with("orientation,orientation_conf,script,"));
+    let mut reader = csv::Reader::from_reader(csv_text.as_bytes());
+    let pid_source_column = reader.headers().unwrap().iter().position(|h| h == "pid_source").unwrap();
+    assert!(reader.records().all(|row| &row.unwrap()[pid_source_column] == "tracker"));
+}
+
+#[test]
+fn bad_requests_get_error_statuses() {
+    let (base_url, _dir) = serve_fixture_scan();
+    for (path, status) in [
+        ("/items/HH999999_0001", 404),
+        ("/collections/HH999999", 404),
+        ("/nope", 404),
+        ("/items?where=nope=1", 400),
+        ("/items?sort=nope", 400),
+        ("/items?limit=ten", 400),
+    ] {
+        let error = ureq::get(&format!("{}{}", base_url, path)).call().unwrap_err();
+        assert!(
+            matches!(error, ureq::Error::StatusCode(code) if code == status),
+            "{}",
+            path
+        );
+    }
+}
+
+// an item is looked up by the key the scanner parsed from its tracker's file-name, not by its image-name
+#[test]
+fn items_are_keyed_by_their_trackers() {
+    let dir = TempDir::new().unwrap();
+    write(
+        dir.path(),
+        "HH001545/HH001545_0001/HH001545_0001-ocr_complete.json",
+        &ocr_json("HH001545_0001").replace("HH001545_0001.jp2", "page.0001.jp2"),
+    );
+    let results = Scanner::builder(dir.path().to_string_lossy()).build().unwrap().scan().unwrap();
+    let base_url = serve(ServedScan::new(
+        results.records,
+        &results.record_paths,
+        Vec::new(),
+        json!({}),
+    ));
+    assert_eq!(
+        get_json(&format!("{}/items/HH001545_0001", base_url))["image_name"],
+        "page.0001.jp2"
+    );
+    let error = ureq::get(&format!("{}/items/page.0001", base_url)).call().unwrap_err();
+    assert!(matches!(error, ureq::Error::StatusCode(404)));
+}
+
+/*  -----------------------------------------------------------------
+    A run's csv, with a promoted extra-field (`dpi`), served via `from_csv()`, gives back the
+    same items as serving that run's records directly.
+    -----------------------------------------------------------------
+*/
+#[test]
+fn a_runs_csv_serves_the_same_items_as_its_records() {
+    let dir = TempDir::new().unwrap();
+    let source: &Path = &dir.path().join("tree");
+    write_item(source, "HH001545_0001", Some("bdr:1"));
+    write_item(source, "HH001545_0002", None);
+    write(
+        source,
+        "HH001546/HH001546_0001/HH001546_0001-ocr_complete.json",
+        &ocr_json("HH001546_0001").replace(r#""orientation": 0"#, r#""dpi": 400, "orientation": 90"#),
+    );
+    let (exit_code, json) = run_cli(&[
+        "--source_dir_path",
+        &source.to_string_lossy(),
+        "--output_dir_path",
+        &dir.path().to_string_lossy(),
+        "--extra_columns",
+        "dpi",
+    ]);
+    assert_eq!(exit_code, Some(0));
+    let csv_path = json["tracker_output_csv_path"].as_str().unwrap();
+
+    let extra_columns: Vec<String> = vec!["dpi".to_string()];
+    let results = Scanner::builder(source.to_string_lossy())
+        .extra_columns(extra_columns.clone())
+        .build()
+        .unwrap()
+        .scan()
+        .unwrap();
+    let expected: Vec<Value> = results
+        .records
+        .iter()
+        .map(|record| Value::Object(helper::csv_row(record, &extra_columns)))
+        .collect();
+
+    let base_url = serve(ServedScan::from_csv(Path::new(csv_path)).unwrap());
+    let summary = get_json(&format!("{}/summary", base_url));
+    assert_eq!(summary["ocr_data_vector_count"], 3);
+    assert_eq!(summary["source_csv_path"], csv_path);
+    let served = get_json(&format!("{}/items?sort=image_name", base_url));
+    assert_eq!(served["items"].as_array().unwrap(), &expected);
+    assert_eq!(served["items"][2]["dpi"], 400);
+    assert_eq!(served["items"][0]["dpi"], Value::Null);
+    let filtered = get_json(&format!("{}/items?where=dpi%3E300", base_url));
+    assert_eq!(filtered["total"], 1);
+}
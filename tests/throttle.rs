@@ -0,0 +1,72 @@
+use parse_ocr_tracker::throttle::IoThrottle;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+#[test]
+fn never_more_than_max_concurrent_reads_permits_are_held() {
+    let throttle = IoThrottle::new(Some(3), None);
+    let held = AtomicUsize::new(0);
+    let max_held = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for _ in 0..8 {
+            scope.spawn(|| {
+                for _ in 0..10 {
+                    let _permit = throttle.acquire();
+                    let now_held: usize = held.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_held.fetch_max(now_held, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(1));
+                    held.fetch_sub(1, Ordering::SeqCst);
+                }
+            });
+        }
+    });
+    assert_eq!(max_held.load(Ordering::SeqCst), 3);
+}
+
+/*  -----------------------------------------------------------------
+    Each read gets its own time-slot, `1 / files_per_second` apart, and doesn't start before it;
+    so the k-th read to start (from 0) starts at least k intervals after the first acquire().
+    -----------------------------------------------------------------
+*/
+#[test]
+fn reads_start_no_faster_than_files_per_second() {
+    let throttle = IoThrottle::new(Some(2), Some(200.0));
+    let interval = Duration::from_millis(5);
+    let start_instant = Instant::now();
+    let read_starts: Mutex<Vec<Instant>> = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..10 {
+                    let _permit = throttle.acquire();
+                    read_starts.lock().unwrap().push(Instant::now());
+                }
+            });
+        }
+    });
+    let mut read_starts: Vec<Instant> = read_starts.into_inner().unwrap();
+    read_starts.sort();
+    assert_eq!(read_starts.len(), 40);
+    for (k, read_start) in read_starts.iter().enumerate() {
+        assert!(
+            *read_start - start_instant >= interval * k as u32,
+            "read {} started early",
+            k
+        );
+    }
+}
+
+#[test]
+fn without_limits_acquire_doesnt_wait() {
+    let throttle = IoThrottle::new(None, None);
+    let start_instant = Instant::now();
+    let permits: Vec<_> = (0..1000).map(|_| throttle.acquire()).collect();
+    assert_eq!(permits.len(), 1000);
+    assert!(start_instant.elapsed() < Duration::from_secs(1));
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use common::{write, OCR_JSON};
+use parse_ocr_tracker::validation::{self, ValidationWarning};
+use parse_ocr_tracker::{helper, Record, Scanner};
+use tempfile::TempDir;
+
+/*  -----------------------------------------------------------------
+    One plausible item, and two that fail checks:
+    - HH001545_0002: sideways-ish orientation, and below-percentages out of order
+    - HH001545_0003: negative word-count, and an avg-confidence over 100
+    -----------------------------------------------------------------
+*/
+fn validation_tree() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    let items = [
+        ("HH001545_0001", OCR_JSON.to_string()),
+        (
+            "HH001545_0002",
+            OCR_JSON.replace(r#""orientation": 0"#, r#""orientation": 45"#).replace("30.0", "1.0"),
+        ),
+        (
+            "HH001545_0003",
+            OCR_JSON.replace("120", "-3").replace("88.5", "120.5"),
+        ),
+    ];
+    for (item, contents) in items {
+        write(
+            dir.path(),
+            &format!("HH001545/{0}/{0}-ocr_complete.json", item),
+            &contents.replace("HH001545_0001", item),
+        );
+    }
+    dir
+}
+
+#[test]
+fn implausible_records_are_flagged_and_tallied() {
+    let dir = validation_tree();
+    let results = Scanner::builder(dir.path().to_string_lossy()).build().unwrap().scan().unwrap();
+    assert_eq!(results.records.len(), 3);
+    assert!(results.records[0].validation_warnings.is_empty());
+    assert_eq!(
+        results.records[1].validation_warnings,
+        [
+            ValidationWarning::BelowNotMonotonic,
+            ValidationWarning::OrientationNotRightAngle
+        ]
+    );
+    let failures = &results.summary.validation_failures;
+    assert_eq!(failures.len(), 4);
+    assert_eq!(failures[&ValidationWarning::NegativeWordCount], 1);
+    assert_eq!(failures[&ValidationWarning::AvgConfidenceOutOfRange], 1);
+    assert_eq!(results.summary.validation_rejected_count, 0);
+
+    // -- the csv-cell is the codes joined by `;`, and reads back the same
+    let mut csv_bytes: Vec<u8> = Vec::new();
+    helper::write_records_csv(&results.records, &[], &mut csv_bytes).unwrap();
+    let csv_text = String::from_utf8(csv_bytes).unwrap();
+    assert!(csv_text.contains(",below_not_monotonic;orientation_not_right_angle\n"));
+    let read_back: Vec<Record> =
+        csv::Reader::from_reader(csv_text.as_bytes()).deserialize().collect::<Result<_, _>>().unwrap();
+    assert_eq!(
+        read_back[2].validation_warnings,
+        validation::validate(&results.records[2])
+    );
+}
+
+#[test]
+fn strict_leaves_implausible_records_out() {
+    let dir = validation_tree();
+    let results =
+        Scanner::builder(dir.path().to_string_lossy()).strict(true).build().unwrap().scan().unwrap();
+    assert_eq!(results.records.len(), 1);
+    assert_eq!(results.records[0].image_name, "HH001545_0001.jp2");
+    assert_eq!(results.invalid_records.len(), 2);
+    assert_eq!(results.summary.record_count, 1);
+    assert_eq!(results.summary.validation_rejected_count, 2);
+    assert_eq!(results.summary.rejected_count, 0); // they parsed; they just aren't plausible
+    assert_eq!(
+        results.summary.validation_failures[&ValidationWarning::BelowNotMonotonic],
+        1
+    );
+}
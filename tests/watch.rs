@@ -0,0 +1,111 @@
+mod common;
+
+use common::{ocr_json, write};
+use parse_ocr_tracker::watch::{self, LiveResults};
+use parse_ocr_tracker::{PidSource, Scanner};
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+use tempfile::TempDir;
+
+fn batch(paths: &[&Path]) -> BTreeSet<PathBuf> {
+    paths.iter().map(|path| path.to_path_buf()).collect()
+}
+
+#[test]
+fn apply_tracks_new_changed_and_deleted_trackers() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path();
+    write(
+        root,
+        "HH001545/HH001545_0001/HH001545_0001-ocr_complete.json",
+        &ocr_json("HH001545_0001"),
+    );
+    let scanner = Scanner::builder(root.to_string_lossy()).build().unwrap();
+    let mut live = LiveResults::from_scan(scanner.scan().unwrap(), scanner.config());
+    assert_eq!(live.summary().record_count, 1);
+
+    // -- a new item-dir: its ocr-tracker is parsed, without a pid yet
+    let item_dir = root.join("HH001545/HH001545_0002");
+    let ocr_path = write(
+        root,
+        "HH001545/HH001545_0002/HH001545_0002-ocr_complete.json",
+        &ocr_json("HH001545_0002"),
+    );
+    let counts = live.apply(&batch(&[&item_dir, &ocr_path]), &scanner);
+    assert_eq!(counts.parsed_count, 1); // not twice, though both the dir and the file changed
+    assert_eq!(live.summary().record_count, 2);
+    let pid_of = |live: &LiveResults| {
+        let record = live.records().find(|r| r.image_name == "HH001545_0002.jp2").unwrap();
+        (record.pid.clone(), record.pid_source)
+    };
+    assert_eq!(pid_of(&live), (None, None));
+
+    // -- its ingest-tracker lands: the existing record gets the pid
+    let ingest_path = write(
+        root,
+        "HH001545/HH001545_0002/HH001545_0002-ingest_complete.json",
+        r#"{"pid": "bdr:2"}"#,
+    );
+    let counts = live.apply(&batch(&[&ingest_path]), &scanner);
+    assert_eq!((counts.parsed_count, counts.pid_changes), (0, 1));
+    assert_eq!(
+        pid_of(&live),
+        (Some("bdr:2".to_string()), Some(PidSource::Tracker))
+    );
+    assert_eq!(live.summary().pid_count, 1);
+
+    // -- the ingest-tracker rewritten with the same pid isn't a pid-change
+    fs::write(&ingest_path, r#"{"pid": "bdr:2"}"#).unwrap();
+    let counts = live.apply(&batch(&[&ingest_path]), &scanner);
+    assert_eq!(counts.pid_changes, 0);
+    assert_eq!(pid_of(&live).0.as_deref(), Some("bdr:2"));
+
+    // -- an ocr-tracker rewritten as junk is rejected
+    fs::write(&ocr_path, "{bad").unwrap();
+    let counts = live.apply(&batch(&[&ocr_path]), &scanner);
+    assert_eq!(counts.rejected_count, 1);
+    assert_eq!(live.summary().record_count, 1);
+    assert_eq!(live.summary().rejected_count, 1);
+
+    // -- a deleted item-dir takes all its paths, and its pid, with it
+    fs::remove_dir_all(&item_dir).unwrap();
+    let counts = live.apply(&batch(&[&item_dir]), &scanner);
+    assert_eq!(counts.removed_count, 2);
+    assert_eq!(live.summary().ocr_tracker_count, 1);
+    assert_eq!(live.summary().rejected_count, 0);
+    assert_eq!(live.summary().pid_count, 0);
+}
+
+#[test]
+fn watch_source_dir_batches_changes_until_stopped() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().to_path_buf();
+    let stop = AtomicBool::new(false);
+    let mut batches: Vec<BTreeSet<PathBuf>> = Vec::new();
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(Duration::from_millis(300)); // let the watcher start
+            write(&root, "HH001545-ocr_complete.json", "{}");
+            thread::sleep(Duration::from_millis(1500));
+            stop.store(true, Ordering::SeqCst);
+        });
+        watch::watch_source_dir(
+            &root,
+            Duration::from_millis(200),
+            || stop.load(Ordering::SeqCst),
+            |changed_paths| {
+                batches.push(changed_paths);
+                Ok(())
+            },
+        )
+        .unwrap();
+    });
+    let changed: BTreeSet<PathBuf> = batches.into_iter().flatten().collect();
+    assert!(changed.contains(&root.join("HH001545-ocr_complete.json")));
+}